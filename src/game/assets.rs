@@ -65,7 +65,9 @@ impl FromWorld for HandleMap<ImageKey> {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
+#[derive(
+    Copy, Clone, Eq, PartialEq, Hash, Reflect, Debug, serde::Serialize, serde::Deserialize,
+)]
 pub enum SfxKey {
     ButtonHover,
     ButtonPress,
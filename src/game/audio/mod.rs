@@ -1,8 +1,76 @@
 pub mod sfx;
 pub mod soundtrack;
 
-use bevy::prelude::*;
+use bevy::{audio::Volume, prelude::*};
+use serde::{Deserialize, Serialize};
 
 pub fn plugin(app: &mut App) {
+    app.register_type::<AudioSettings>()
+        .init_resource::<AudioSettings>();
     app.add_plugins((sfx::plugin, soundtrack::plugin));
 }
+
+/// A volume slider, every sound plays at master times its own bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum VolumeBus {
+    Master,
+    Music,
+    Sfx,
+}
+
+/// Resource of the player's volume settings, saved to the store when the audio menu closes
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        // the old fixed global volume, so the mix sounds the same until it is changed
+        AudioSettings {
+            master: 0.3,
+            music: 1.0,
+            sfx: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    /// Amount each press of a slider button moves it
+    pub const STEP: f32 = 0.1;
+
+    pub fn get(&self, bus: VolumeBus) -> f32 {
+        match bus {
+            VolumeBus::Master => self.master,
+            VolumeBus::Music => self.music,
+            VolumeBus::Sfx => self.sfx,
+        }
+    }
+
+    /// Moves a slider by `amount`, kept between silent and full
+    pub fn adjust(&mut self, bus: VolumeBus, amount: f32) {
+        let slider = match bus {
+            VolumeBus::Master => &mut self.master,
+            VolumeBus::Music => &mut self.music,
+            VolumeBus::Sfx => &mut self.sfx,
+        };
+        // rounded so repeated steps land back on the same values
+        *slider = ((*slider + amount).clamp(0., 1.) * 100.).round() / 100.;
+    }
+
+    /// The volume a sound on `bus` should play at
+    pub fn volume(&self, bus: VolumeBus) -> Volume {
+        if self.muted {
+            return Volume::ZERO;
+        }
+        match bus {
+            VolumeBus::Master => Volume::new(self.master),
+            _ => Volume::new(self.master * self.get(bus)),
+        }
+    }
+}
@@ -3,19 +3,21 @@ use rand::{seq::SliceRandom, Rng};
 
 use crate::game::assets::{HandleMap, SfxKey};
 
+use super::{AudioSettings, VolumeBus};
+
 pub(super) fn plugin(app: &mut App) {
     app.observe(play_sfx);
+    app.observe(play_sfx_at);
 }
 
 fn play_sfx(
     trigger: Trigger<PlaySfx>,
     mut commands: Commands,
     sfx_handles: Res<HandleMap<SfxKey>>,
+    settings: Res<AudioSettings>,
 ) {
-    let sfx_key = match trigger.event() {
-        PlaySfx::Key(key) => *key,
-        PlaySfx::RandomStep => random_step(),
-        PlaySfx::BlockHit => random_hit(),
+    let Some(sfx_key) = trigger.event().key() else {
+        return;
     };
     commands.spawn((
         Name::new("SFX Source"),
@@ -23,18 +25,69 @@ fn play_sfx(
             source: sfx_handles[&sfx_key].clone_weak(),
             settings: PlaybackSettings {
                 mode: PlaybackMode::Despawn,
+                volume: settings.volume(VolumeBus::Sfx),
                 ..default()
             },
         },
     ));
 }
 
+/// Sounds placed in the world are heard from where they happen by the [`SpatialListener`]
+fn play_sfx_at(
+    trigger: Trigger<PlaySfxAt>,
+    mut commands: Commands,
+    sfx_handles: Res<HandleMap<SfxKey>>,
+    settings: Res<AudioSettings>,
+) {
+    let Some(sfx_key) = trigger.event().sfx.key() else {
+        return;
+    };
+    commands.spawn((
+        Name::new("Spatial SFX Source"),
+        AudioSourceBundle {
+            source: sfx_handles[&sfx_key].clone_weak(),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                volume: settings.volume(VolumeBus::Sfx),
+                spatial: true,
+                ..default()
+            },
+        },
+        TransformBundle::from_transform(Transform::from_translation(trigger.event().pos)),
+    ));
+}
+
 /// Trigger this event to play a single sound effect.
-#[derive(Event)]
+#[derive(Event, Debug, Clone)]
 pub enum PlaySfx {
     Key(SfxKey),
     RandomStep,
     BlockHit,
+    /// One of these picked at random, nothing when empty
+    OneOf(Vec<SfxKey>),
+}
+
+impl PlaySfx {
+    /// Plays the sound from `pos` instead of everywhere at once
+    pub fn at(self, pos: Vec3) -> PlaySfxAt {
+        PlaySfxAt { sfx: self, pos }
+    }
+
+    fn key(&self) -> Option<SfxKey> {
+        match self {
+            PlaySfx::Key(key) => Some(*key),
+            PlaySfx::RandomStep => Some(random_step()),
+            PlaySfx::BlockHit => Some(random_hit()),
+            PlaySfx::OneOf(keys) => keys.choose(&mut rand::thread_rng()).copied(),
+        }
+    }
+}
+
+/// Trigger this event to play a sound effect from a point in the world.
+#[derive(Event, Debug, Clone)]
+pub struct PlaySfxAt {
+    pub sfx: PlaySfx,
+    pub pos: Vec3,
 }
 
 fn random_step() -> SfxKey {
@@ -1,48 +1,231 @@
+//! Playlists for each part of the game, crossfaded when the context changes.
+//!
+//! A playlist plays its tracks in order and starts again after the last one. It can also have a
+//! layer that loops over the top, louder the busier the player's factory is.
+
 use bevy::{audio::PlaybackMode, prelude::*};
 
-use crate::game::assets::{HandleMap, SoundtrackKey};
+use crate::{
+    game::assets::{HandleMap, SoundtrackKey},
+    screen::{
+        voxel_world::{voxel_util::WorldType, world::energy::EnergyNetworks},
+        Screen,
+    },
+};
+
+use super::{AudioSettings, VolumeBus};
+
+/// Seconds for one soundtrack to fade into the next
+const CROSSFADE_SECS: f32 = 2.;
+/// Energy used in a tick for the intensity layer to play at full volume
+const BUSY_FACTORY: f32 = 20.;
 
 pub(super) fn plugin(app: &mut App) {
-    app.register_type::<IsSoundtrack>();
+    app.register_type::<IsSoundtrack>()
+        .init_resource::<Soundtrack>()
+        .init_resource::<SoundtrackIntensity>();
     app.observe(play_soundtrack);
+    app.add_systems(
+        Update,
+        (follow_factory_activity, next_track, fade_soundtrack).chain(),
+    );
+}
+
+/// The part of the game a playlist is chosen for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundtrackContext {
+    Title,
+    HexMap,
+    VoxelWorld(WorldType),
+    Credits,
+}
+
+struct Playlist {
+    tracks: &'static [SoundtrackKey],
+    /// looped with the tracks and faded in by [`SoundtrackIntensity`]
+    layer: Option<SoundtrackKey>,
+}
+
+impl SoundtrackContext {
+    fn playlist(&self) -> Playlist {
+        let tracks: &'static [SoundtrackKey] = match self {
+            SoundtrackContext::Title | SoundtrackContext::Credits => &[SoundtrackKey::Credits],
+            SoundtrackContext::HexMap => &[SoundtrackKey::Gameplay, SoundtrackKey::Credits],
+            SoundtrackContext::VoxelWorld(WorldType::Empty | WorldType::Sand) => {
+                &[SoundtrackKey::Gameplay]
+            }
+            SoundtrackContext::VoxelWorld(_) => &[SoundtrackKey::Gameplay, SoundtrackKey::Credits],
+        };
+        // layers need stems made to match their tracks, none are shipped yet
+        Playlist {
+            tracks,
+            layer: None,
+        }
+    }
+}
+
+/// Resource for the playlist that is playing and how far through it is
+#[derive(Resource, Default)]
+struct Soundtrack {
+    context: Option<SoundtrackContext>,
+    track: usize,
+}
+
+/// Resource from 0 to 1 for how loud intensity layers play
+#[derive(Resource, Default)]
+pub struct SoundtrackIntensity(pub f32);
+
+fn spawn_track(
+    commands: &mut Commands,
+    handles: &HandleMap<SoundtrackKey>,
+    key: SoundtrackKey,
+    layer: bool,
+) {
+    commands.spawn((
+        Name::new("Soundtrack"),
+        AudioSourceBundle {
+            source: handles[&key].clone_weak(),
+            settings: PlaybackSettings {
+                // layers keep going under the tracks, tracks are swapped by `next_track`
+                mode: if layer {
+                    PlaybackMode::Loop
+                } else {
+                    PlaybackMode::Once
+                },
+                // faded up by `fade_soundtrack`
+                volume: bevy::audio::Volume::ZERO,
+                ..default()
+            },
+        },
+        IsSoundtrack { layer },
+    ));
 }
 
 fn play_soundtrack(
     trigger: Trigger<PlaySoundtrack>,
     mut commands: Commands,
     soundtrack_handles: Res<HandleMap<SoundtrackKey>>,
-    soundtrack_query: Query<Entity, With<IsSoundtrack>>,
+    soundtrack_query: Query<Entity, (With<IsSoundtrack>, Without<FadingOut>)>,
+    mut soundtrack: ResMut<Soundtrack>,
 ) {
+    let context = match trigger.event() {
+        PlaySoundtrack::Context(context) => Some(*context),
+        PlaySoundtrack::Disable => None,
+    };
+    // coming back to the same context, like the title from the options, keeps the music going
+    if context == soundtrack.context {
+        return;
+    }
     for entity in &soundtrack_query {
+        commands.entity(entity).insert(FadingOut);
+    }
+    *soundtrack = Soundtrack { context, track: 0 };
+
+    let Some(context) = context else {
+        return;
+    };
+    let playlist = context.playlist();
+    spawn_track(
+        &mut commands,
+        &soundtrack_handles,
+        playlist.tracks[0],
+        false,
+    );
+    if let Some(layer) = playlist.layer {
+        spawn_track(&mut commands, &soundtrack_handles, layer, true);
+    }
+}
+
+/// Starts the next track of the playlist once the last one has finished
+fn next_track(
+    mut commands: Commands,
+    soundtrack_handles: Res<HandleMap<SoundtrackKey>>,
+    tracks: Query<(Entity, &AudioSink, &IsSoundtrack), Without<FadingOut>>,
+    mut soundtrack: ResMut<Soundtrack>,
+) {
+    let Some(context) = soundtrack.context else {
+        return;
+    };
+    for (entity, sink, track) in &tracks {
+        if track.layer || !sink.empty() {
+            continue;
+        }
         commands.entity(entity).despawn_recursive();
+        let playlist = context.playlist();
+        soundtrack.track = (soundtrack.track + 1) % playlist.tracks.len();
+        spawn_track(
+            &mut commands,
+            &soundtrack_handles,
+            playlist.tracks[soundtrack.track],
+            false,
+        );
     }
+}
 
-    let soundtrack_key = match trigger.event() {
-        PlaySoundtrack::Key(key) => *key,
-        PlaySoundtrack::Disable => return,
+/// The intensity follows the energy machines used on the last tick
+fn follow_factory_activity(
+    time: Res<Time>,
+    screen: Res<State<Screen>>,
+    networks: Option<Res<EnergyNetworks>>,
+    mut intensity: ResMut<SoundtrackIntensity>,
+) {
+    let target = match networks {
+        Some(networks) if *screen.get() == Screen::VoxelWorld => {
+            (networks.consumed() as f32 / BUSY_FACTORY).min(1.)
+        }
+        _ => 0.,
     };
-    commands.spawn((
-        AudioSourceBundle {
-            source: soundtrack_handles[&soundtrack_key].clone_weak(),
-            settings: PlaybackSettings {
-                mode: PlaybackMode::Loop,
-                ..default()
-            },
-        },
-        IsSoundtrack,
-    ));
+    // eased so single ticks of work do not make the layer flicker
+    intensity.0 += (target - intensity.0) * (time.delta_seconds() / CROSSFADE_SECS).min(1.);
+}
+
+/// Moves every soundtrack toward its volume, removing the ones that have faded out
+fn fade_soundtrack(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<AudioSettings>,
+    intensity: Res<SoundtrackIntensity>,
+    tracks: Query<(Entity, &AudioSink, &IsSoundtrack, Has<FadingOut>)>,
+) {
+    let music = settings.volume(VolumeBus::Music).get();
+    let step = music.max(0.01) * time.delta_seconds() / CROSSFADE_SECS;
+    for (entity, sink, track, fading) in &tracks {
+        let target = if fading {
+            0.
+        } else if track.layer {
+            music * intensity.0
+        } else {
+            music
+        };
+        let volume = sink.volume();
+        let volume = if volume < target {
+            (volume + step).min(target)
+        } else {
+            (volume - step).max(target)
+        };
+        sink.set_volume(volume);
+        if fading && volume <= 0. {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
 }
 
-/// Trigger this event to play or disable the soundtrack.
-/// Playing a new soundtrack will overwrite the previous one.
-/// Soundtracks will loop.
+/// Trigger this event to play the playlist for a context or disable the soundtrack.
+/// The new playlist fades in as the previous one fades out.
 #[derive(Event)]
 pub enum PlaySoundtrack {
-    Key(SoundtrackKey),
+    Context(SoundtrackContext),
     Disable,
 }
 
 /// Marker component for the soundtrack entity so we can find it later.
 #[derive(Component, Reflect)]
 #[reflect(Component)]
-struct IsSoundtrack;
+struct IsSoundtrack {
+    /// layers follow the intensity instead of playing at full volume
+    layer: bool,
+}
+
+/// Soundtracks that are fading out, despawned once silent
+#[derive(Component)]
+struct FadingOut;
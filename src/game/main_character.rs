@@ -1,5 +1,6 @@
 use bevy::{ecs::query::QueryData, prelude::*};
-use leafwing_input_manager::prelude::*;
+use leafwing_input_manager::{axislike::AxisType, prelude::*};
+use strum::IntoEnumIterator;
 
 use crate::screen::inventory::Inventory;
 
@@ -25,7 +26,7 @@ pub fn spawn_main_player(mut commands: Commands) {
     ));
 }
 
-fn default_player_inputs() -> InputMap<PlayerAction> {
+pub fn default_player_inputs() -> InputMap<PlayerAction> {
     let mut map = InputMap::default();
 
     map.insert(
@@ -115,7 +116,7 @@ fn default_player_inputs() -> InputMap<PlayerAction> {
 
     map.insert(
         PlayerAction::MoveRight,
-        UserInput::Single(InputKind::PhysicalKey(KeyCode::ArrowDown)),
+        UserInput::Single(InputKind::PhysicalKey(KeyCode::ArrowRight)),
     );
 
     map.insert(
@@ -171,6 +172,56 @@ fn default_player_inputs() -> InputMap<PlayerAction> {
         UserInput::Single(InputKind::GamepadButton(GamepadButtonType::LeftTrigger)),
     );
 
+    map.insert(
+        PlayerAction::QuestLog,
+        UserInput::Single(InputKind::PhysicalKey(KeyCode::KeyJ)),
+    );
+
+    map.insert(
+        PlayerAction::QuestLog,
+        UserInput::Single(InputKind::GamepadButton(GamepadButtonType::Select)),
+    );
+
+    map.insert(
+        PlayerAction::Blueprint,
+        UserInput::Single(InputKind::PhysicalKey(KeyCode::KeyB)),
+    );
+
+    map.insert(
+        PlayerAction::Blueprint,
+        UserInput::Single(InputKind::GamepadButton(GamepadButtonType::DPadLeft)),
+    );
+
+    map.insert(
+        PlayerAction::BlueprintNext,
+        UserInput::Single(InputKind::PhysicalKey(KeyCode::KeyN)),
+    );
+
+    map.insert(
+        PlayerAction::BlueprintNext,
+        UserInput::Single(InputKind::GamepadButton(GamepadButtonType::DPadRight)),
+    );
+
+    map.insert(
+        PlayerAction::Rotate,
+        UserInput::Single(InputKind::PhysicalKey(KeyCode::KeyR)),
+    );
+
+    map.insert(
+        PlayerAction::Rotate,
+        UserInput::Single(InputKind::GamepadButton(GamepadButtonType::North)),
+    );
+
+    map.insert(
+        PlayerAction::EnergyOverlay,
+        UserInput::Single(InputKind::PhysicalKey(KeyCode::KeyO)),
+    );
+
+    map.insert(
+        PlayerAction::EnergyOverlay,
+        UserInput::Single(InputKind::GamepadButton(GamepadButtonType::RightThumb)),
+    );
+
     #[cfg(feature = "dev")]
     map.insert(
         PlayerAction::ToolbarPrev,
@@ -188,5 +239,152 @@ fn default_player_inputs() -> InputMap<PlayerAction> {
         UserInput::Single(InputKind::GamepadButton(GamepadButtonType::DPadDown)),
     );
 
+    map.insert(
+        PlayerAction::Compress,
+        UserInput::Single(InputKind::PhysicalKey(KeyCode::KeyC)),
+    );
+
+    map.insert(
+        PlayerAction::Inventory,
+        UserInput::Single(InputKind::PhysicalKey(KeyCode::KeyT)),
+    );
+
+    map.insert(
+        PlayerAction::Inventory,
+        UserInput::Single(InputKind::GamepadButton(GamepadButtonType::Start)),
+    );
+
+    map.insert(
+        PlayerAction::ToggleCursor,
+        UserInput::Single(InputKind::PhysicalKey(KeyCode::Backquote)),
+    );
+
+    map.insert(
+        PlayerAction::ClearInventory,
+        UserInput::Single(InputKind::PhysicalKey(KeyCode::NumpadSubtract)),
+    );
+
+    let digits = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+        KeyCode::Digit0,
+    ];
+    for (slot, digit) in PlayerAction::SLOTS.into_iter().zip(digits) {
+        map.insert(slot, UserInput::Single(InputKind::PhysicalKey(digit)));
+    }
+    // shift and a digit picks the row, the longer chord wins over the slot
+    for (row, digit) in PlayerAction::ROWS.into_iter().zip(digits) {
+        map.insert(
+            row,
+            UserInput::Chord(vec![
+                InputKind::Modifier(Modifier::Shift),
+                InputKind::PhysicalKey(digit),
+            ]),
+        );
+    }
+
     map
 }
+
+/// The devices bindings can be reset for separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputDevice {
+    KeyboardMouse,
+    Gamepad,
+}
+
+fn is_gamepad_kind(kind: &InputKind) -> bool {
+    match kind {
+        InputKind::GamepadButton(_) => true,
+        InputKind::SingleAxis(axis) => matches!(axis.axis_type, AxisType::Gamepad(_)),
+        InputKind::DualAxis(axis) => matches!(axis.x.axis_type, AxisType::Gamepad(_)),
+        _ => false,
+    }
+}
+
+impl InputDevice {
+    pub fn of(input: &UserInput) -> Self {
+        let gamepad = match input {
+            UserInput::Single(kind) => is_gamepad_kind(kind),
+            UserInput::Chord(kinds) => kinds.iter().any(is_gamepad_kind),
+            UserInput::VirtualDPad(dpad) => is_gamepad_kind(&dpad.up),
+            UserInput::VirtualAxis(axis) => is_gamepad_kind(&axis.positive),
+        };
+        if gamepad {
+            InputDevice::Gamepad
+        } else {
+            InputDevice::KeyboardMouse
+        }
+    }
+}
+
+/// Puts the default bindings for `device` back, keeping what is bound on the other device
+pub fn reset_device_bindings(map: &mut InputMap<PlayerAction>, device: InputDevice) {
+    let defaults = default_player_inputs();
+    for action in PlayerAction::iter() {
+        let kept = map
+            .get(&action)
+            .into_iter()
+            .flatten()
+            .filter(|input| InputDevice::of(input) != device);
+        let reset = defaults
+            .get(&action)
+            .into_iter()
+            .flatten()
+            .filter(|input| InputDevice::of(input) == device);
+        let bindings = kept.chain(reset).cloned().collect::<Vec<_>>();
+        map.clear_action(&action);
+        for input in bindings {
+            map.insert(action, input);
+        }
+    }
+}
+
+/// Every input bound to more than one action, with the actions it is bound to
+pub fn binding_conflicts(map: &InputMap<PlayerAction>) -> Vec<(UserInput, Vec<PlayerAction>)> {
+    let mut bound: Vec<(UserInput, Vec<PlayerAction>)> = Vec::new();
+    for action in PlayerAction::iter() {
+        for input in map.get(&action).into_iter().flatten() {
+            match bound.iter_mut().find(|(other, _)| other == input) {
+                Some((_, actions)) => actions.push(action),
+                None => bound.push((input.clone(), vec![action])),
+            }
+        }
+    }
+    bound.retain(|(_, actions)| actions.len() > 1);
+    bound
+}
+
+#[test]
+fn default_bindings_do_not_conflict() {
+    assert_eq!(binding_conflicts(&default_player_inputs()), Vec::new());
+}
+
+#[test]
+fn reset_only_touches_one_device() {
+    let mut map = default_player_inputs();
+    let jump_key = UserInput::Single(InputKind::PhysicalKey(KeyCode::Space));
+    let jump_button = UserInput::Single(InputKind::GamepadButton(GamepadButtonType::West));
+    map.clear_action(&PlayerAction::Jump);
+    map.insert(
+        PlayerAction::Jump,
+        UserInput::Single(InputKind::PhysicalKey(KeyCode::KeyK)),
+    );
+    reset_device_bindings(&mut map, InputDevice::Gamepad);
+    let jump = map.get(&PlayerAction::Jump).cloned().unwrap_or_default();
+    assert!(jump.contains(&jump_button));
+    assert!(!jump.contains(&jump_key));
+
+    reset_device_bindings(&mut map, InputDevice::KeyboardMouse);
+    let jump = map.get(&PlayerAction::Jump).cloned().unwrap_or_default();
+    assert!(jump.contains(&jump_key));
+    assert!(jump.contains(&jump_button));
+    assert_eq!(jump.len(), 2);
+}
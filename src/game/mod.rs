@@ -2,6 +2,7 @@ pub mod assets;
 pub mod audio;
 pub mod main_character;
 pub mod save;
+pub mod video;
 
 use crate::screen::{
     hex_vox_util::{HexId, MapDirection},
@@ -10,15 +11,16 @@ use crate::screen::{
 use bevy::{
     app::{App, Startup},
     asset::Handle,
-    prelude::{Component, IntoSystemConfigs, Resource},
+    prelude::{Component, IntoSystemConfigs, Query, Resource, With},
     reflect::Reflect,
 };
+use leafwing_input_manager::prelude::ActionState;
 use main_character::spawn_main_player;
 use save::inventory_load;
 
 ///Loaded
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((audio::plugin, assets::plugin, save::plugin));
+    app.add_plugins((audio::plugin, assets::plugin, save::plugin, video::plugin));
     app.insert_resource(HexSelect {
         hex_id: HexId::new(0, 0),
         direction: MapDirection::Up,
@@ -71,4 +73,66 @@ pub enum PlayerAction {
     ToolbarPrev,
     ItemInc,
     ItemDec,
+    QuestLog,
+    Blueprint,
+    BlueprintNext,
+    Rotate,
+    EnergyOverlay,
+    Compress,
+    Inventory,
+    ToggleCursor,
+    ClearInventory,
+    Slot1,
+    Slot2,
+    Slot3,
+    Slot4,
+    Slot5,
+    Slot6,
+    Slot7,
+    Slot8,
+    Slot9,
+    Slot10,
+    Row1,
+    Row2,
+    Row3,
+    Row4,
+    Row5,
+    Row6,
+}
+
+impl PlayerAction {
+    /// Selects the hotbar slot at the same index
+    pub const SLOTS: [PlayerAction; 10] = [
+        PlayerAction::Slot1,
+        PlayerAction::Slot2,
+        PlayerAction::Slot3,
+        PlayerAction::Slot4,
+        PlayerAction::Slot5,
+        PlayerAction::Slot6,
+        PlayerAction::Slot7,
+        PlayerAction::Slot8,
+        PlayerAction::Slot9,
+        PlayerAction::Slot10,
+    ];
+
+    /// Selects the inventory row at the same index
+    pub const ROWS: [PlayerAction; 6] = [
+        PlayerAction::Row1,
+        PlayerAction::Row2,
+        PlayerAction::Row3,
+        PlayerAction::Row4,
+        PlayerAction::Row5,
+        PlayerAction::Row6,
+    ];
+}
+
+/// Run condition for when the player has just pressed `action`, like [`input_just_pressed`](bevy::input::common_conditions::input_just_pressed) for keys
+pub fn action_just_pressed(
+    action: PlayerAction,
+) -> impl FnMut(Query<&ActionState<PlayerAction>, With<main_character::Player>>) -> bool + Clone {
+    move |input: Query<&ActionState<PlayerAction>, With<main_character::Player>>| {
+        input
+            .get_single()
+            .is_ok_and(|input| input.just_pressed(&action))
+    }
 }
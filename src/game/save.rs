@@ -1,22 +1,39 @@
 use std::hash::{DefaultHasher, Hash, Hasher};
 
 use bevy::{
-    app::{App, PostStartup, Startup},
+    app::{App, PostStartup, PostUpdate, Startup, Update},
     asset::Assets,
     log::{error, info, warn},
-    prelude::{Commands, Entity, OnEnter, OnExit, Query, ReflectResource, Res, Resource, With},
+    prelude::{
+        in_state, resource_changed, Commands, Entity, EventReader, IntoSystemConfigs, OnEnter,
+        OnExit, Query, ReflectResource, Res, ResMut, Resource, With,
+    },
     reflect::Reflect,
+    time::{Time, Timer, TimerMode},
 };
 use leafwing_input_manager::prelude::InputMap;
 use serde::{Deserialize, Serialize};
 
 use crate::screen::{
-    inventory::Inventory,
-    voxel_world::world::{VoxelChunk, VoxelStore},
-    Screen,
+    inventory::{Inventory, InventorySlot},
+    quests::QuestLog,
+    voxel_world::{
+        player_controller::VoxelSettings,
+        voxels::LegacyBlockType,
+        world::{
+            block_changes::BlockChanged,
+            blueprint::Blueprints,
+            loot::DropSettings,
+            multi_block::{MultiBlockOrigin, MultiBlockOrigins},
+            VoxelChunk, VoxelStore,
+        },
+    },
+    Score, Screen, Target,
 };
 
-use super::{main_character::Player, HexSelect, PlayerAction};
+use super::{
+    audio::AudioSettings, main_character::Player, video::VideoSettings, HexSelect, PlayerAction,
+};
 
 #[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect, Serialize, Deserialize)]
 #[reflect(Resource)]
@@ -80,6 +97,9 @@ pub fn inventory_load(
         let inventory = if let Ok(inventory) = pkv.get::<Inventory>("inventory") {
             info!("Inventory is {:?}", inventory);
             inventory
+        } else if let Ok(legacy) = pkv.get::<LegacyInventory>("inventory") {
+            info!("Migrated inventory to store orientations apart from blocks");
+            legacy.into()
         } else {
             return;
         };
@@ -96,6 +116,37 @@ pub fn inventory_load(
     }
 }
 
+/// The inventory format from before orientations were split from [`crate::screen::voxel_world::voxels::BlockType`]
+#[derive(Deserialize)]
+struct LegacyInventory {
+    slots: Vec<LegacyInventorySlot>,
+    selected_slot: usize,
+    selected_row: usize,
+}
+
+#[derive(Deserialize)]
+struct LegacyInventorySlot {
+    resource_type: Option<LegacyBlockType>,
+    quantity: u32,
+}
+
+impl From<LegacyInventory> for Inventory {
+    fn from(legacy: LegacyInventory) -> Self {
+        Inventory {
+            slots: legacy
+                .slots
+                .into_iter()
+                .map(|slot| InventorySlot {
+                    resource_type: slot.resource_type.map(|block| block.migrate().0),
+                    quantity: slot.quantity,
+                })
+                .collect(),
+            selected_slot: legacy.selected_slot,
+            selected_row: legacy.selected_row,
+        }
+    }
+}
+
 /// This setup should be used to get the seed from save data if the player has played, or generate a new seed if they haven't
 pub fn inventory_save(pkv: Res<VoxelStore>, player_inventory: Query<&Inventory, With<Player>>) {
     if let Some(mut pkv) = pkv.write() {
@@ -126,17 +177,189 @@ pub fn save_chunk_data(
     }
 }
 
+/// Resource to save the open chunk a little while after it changes
+#[derive(Resource)]
+pub struct ChunkAutosave {
+    dirty: bool,
+    timer: Timer,
+}
+
+impl Default for ChunkAutosave {
+    fn default() -> Self {
+        ChunkAutosave {
+            dirty: false,
+            timer: Timer::from_seconds(30., TimerMode::Repeating),
+        }
+    }
+}
+
+fn chunk_autosave(
+    time: Res<Time>,
+    mut autosave: ResMut<ChunkAutosave>,
+    mut changed: EventReader<BlockChanged>,
+    store: Res<VoxelStore>,
+    chunks: Res<Assets<VoxelChunk>>,
+    origins: Res<MultiBlockOrigins>,
+    selected: Res<HexSelect>,
+) {
+    if changed.read().count() > 0 {
+        autosave.dirty = true;
+    }
+    if !autosave.timer.tick(time.delta()).just_finished() || !autosave.dirty {
+        return;
+    }
+    autosave.dirty = false;
+    if let Some(mut store) = store.write() {
+        if let Err(e) = store.set(multi_block_key(&selected), &origins.to_saved()) {
+            error!("Machines not saved {e}");
+        }
+    }
+    save_chunk_data(store, chunks, selected);
+}
+
+/// Loads the players delivery progress, new games start on the first goal
+fn progress_load(mut commands: Commands, store: Res<VoxelStore>) {
+    let Some(store) = store.read() else {
+        error!("failed to get pkv store");
+        return;
+    };
+    if let Ok(score) = store.get::<Score>("score") {
+        commands.insert_resource(score);
+    }
+    if let Ok(target) = store.get::<Target>("target") {
+        commands.insert_resource(target);
+    }
+}
+
+pub fn progress_save(store: Res<VoxelStore>, score: Res<Score>, target: Res<Target>) {
+    if let Some(mut store) = store.write() {
+        if store.set("score", &*score).is_err() {
+            error!("Failed to save score");
+        }
+        if store.set("target", &*target).is_err() {
+            error!("Failed to save target");
+        }
+    } else {
+        warn!("Failed to write progress to store");
+    }
+}
+
+fn quest_load(mut commands: Commands, store: Res<VoxelStore>) {
+    let Some(store) = store.read() else {
+        error!("failed to get pkv store");
+        return;
+    };
+    if let Ok(quests) = store.get::<QuestLog>("quests") {
+        commands.insert_resource(quests);
+    }
+}
+
+pub fn quest_save(store: Res<VoxelStore>, quests: Res<QuestLog>) {
+    if let Some(mut store) = store.write() {
+        if store.set("quests", &*quests).is_err() {
+            error!("Failed to save quests");
+        }
+    } else {
+        warn!("Failed to write quests to store");
+    }
+}
+
+fn blueprint_load(mut commands: Commands, store: Res<VoxelStore>) {
+    let Some(store) = store.read() else {
+        error!("failed to get pkv store");
+        return;
+    };
+    if let Ok(blueprints) = store.get::<Blueprints>("blueprints") {
+        commands.insert_resource(blueprints);
+    }
+}
+
+pub fn blueprint_save(store: Res<VoxelStore>, blueprints: Res<Blueprints>) {
+    if let Some(mut store) = store.write() {
+        if store.set("blueprints", &*blueprints).is_err() {
+            error!("Failed to save blueprints");
+        }
+    } else {
+        warn!("Failed to write blueprints to store");
+    }
+}
+
+fn multi_block_key(selected: &HexSelect) -> String {
+    format!("machines{}", selected.hex_id)
+}
+
+pub fn multi_block_save(
+    store: Res<VoxelStore>,
+    origins: Res<MultiBlockOrigins>,
+    selected: Res<HexSelect>,
+) {
+    if let Some(mut store) = store.write() {
+        if let Err(e) = store.set(multi_block_key(&selected), &origins.to_saved()) {
+            error!("Machines not saved {e}");
+        }
+    } else {
+        warn!("Failed to write machines to store");
+    }
+}
+
+pub fn multi_block_load(
+    store: Res<VoxelStore>,
+    mut origins: ResMut<MultiBlockOrigins>,
+    selected: Res<HexSelect>,
+) {
+    let Some(store) = store.read() else {
+        error!("failed to get pkv store");
+        return;
+    };
+    let saved = store
+        .get::<Vec<MultiBlockOrigin>>(&multi_block_key(&selected))
+        .unwrap_or_default();
+    *origins = MultiBlockOrigins::from_saved(saved);
+}
+
 pub(super) fn plugin(app: &mut App) {
     // This initializes as Company, Game to set store locations
-    app.add_systems(Startup, seed_load_and_save)
-        .add_systems(PostStartup, keybind_load)
-        // save the keybind every time you exit the menu
-        .add_systems(
-            OnExit(Screen::Options(
-                crate::screen::options::OptionMenus::KeyBinding,
-            )),
-            keybind_save,
-        );
+    app.add_systems(
+        Startup,
+        (
+            seed_load_and_save,
+            progress_load,
+            quest_load,
+            blueprint_load,
+            audio_settings_load,
+            video_settings_load,
+        ),
+    )
+    .add_systems(PostStartup, keybind_load)
+    .init_resource::<ChunkAutosave>()
+    .add_systems(OnEnter(Screen::VoxelWorld), multi_block_load)
+    .add_systems(
+        PostUpdate,
+        chunk_autosave.run_if(in_state(Screen::VoxelWorld)),
+    )
+    .add_systems(
+        Update,
+        blueprint_save.run_if(resource_changed::<Blueprints>),
+    )
+    .add_systems(
+        OnExit(Screen::VoxelWorld),
+        (multi_block_save, progress_save, quest_save),
+    )
+    // save the keybind every time you exit the menu
+    .add_systems(
+        OnExit(Screen::Options(
+            crate::screen::options::OptionMenus::KeyBinding,
+        )),
+        keybind_save,
+    )
+    .add_systems(
+        OnExit(Screen::Options(crate::screen::options::OptionMenus::Audio)),
+        audio_settings_save,
+    )
+    .add_systems(
+        OnExit(Screen::Options(crate::screen::options::OptionMenus::Video)),
+        video_settings_save,
+    );
 }
 
 pub fn keybind_save(player: Query<&InputMap<PlayerAction>>, store: Res<VoxelStore>) {
@@ -166,3 +389,60 @@ pub fn keybind_load(
         };
     }
 }
+
+pub fn audio_settings_save(store: Res<VoxelStore>, settings: Res<AudioSettings>) {
+    if let Some(mut store) = store.write() {
+        if store.set("AudioSettings", &*settings).is_err() {
+            error!("Failed to save audio settings");
+        }
+    } else {
+        warn!("Failed to write audio settings to store");
+    }
+}
+
+fn audio_settings_load(mut commands: Commands, store: Res<VoxelStore>) {
+    let Some(store) = store.read() else {
+        error!("failed to get pkv store");
+        return;
+    };
+    if let Ok(settings) = store.get::<AudioSettings>("AudioSettings") {
+        commands.insert_resource(settings);
+    }
+}
+
+pub fn video_settings_save(
+    store: Res<VoxelStore>,
+    video: Res<VideoSettings>,
+    controls: Res<VoxelSettings>,
+    drops: Res<DropSettings>,
+) {
+    if let Some(mut store) = store.write() {
+        if store.set("VideoSettings", &*video).is_err() {
+            error!("Failed to save video settings");
+        }
+        if store.set("ControlSettings", &*controls).is_err() {
+            error!("Failed to save control settings");
+        }
+        if store.set("DropSettings", &*drops).is_err() {
+            error!("Failed to save drop settings");
+        }
+    } else {
+        warn!("Failed to write video settings to store");
+    }
+}
+
+fn video_settings_load(mut commands: Commands, store: Res<VoxelStore>) {
+    let Some(store) = store.read() else {
+        error!("failed to get pkv store");
+        return;
+    };
+    if let Ok(video) = store.get::<VideoSettings>("VideoSettings") {
+        commands.insert_resource(video);
+    }
+    if let Ok(controls) = store.get::<VoxelSettings>("ControlSettings") {
+        commands.insert_resource(controls);
+    }
+    if let Ok(drops) = store.get::<DropSettings>("DropSettings") {
+        commands.insert_resource(drops);
+    }
+}
@@ -0,0 +1,186 @@
+//! Window and camera settings the player can change from the options, applied as soon as they change.
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+    },
+    window::{PresentMode, PrimaryWindow, WindowMode, WindowRef, WindowResized},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::screen::{voxel_world::voxel_util::VoxelPlayer, Screen};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<VideoSettings>()
+        .init_resource::<VideoSettings>()
+        .add_systems(
+            Update,
+            (
+                apply_window_settings.run_if(resource_changed::<VideoSettings>),
+                apply_camera_settings,
+                apply_render_scale,
+            ),
+        );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum DisplayMode {
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    /// The mode after this one, for a button that cycles through them
+    pub fn next(self) -> Self {
+        match self {
+            DisplayMode::Windowed => DisplayMode::Borderless,
+            DisplayMode::Borderless => DisplayMode::Fullscreen,
+            DisplayMode::Fullscreen => DisplayMode::Windowed,
+        }
+    }
+
+    fn window_mode(self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+}
+
+/// Resource of the player's video settings, saved to the store when the video menu closes
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct VideoSettings {
+    pub display_mode: DisplayMode,
+    pub vsync: bool,
+    /// The voxel world is drawn at this fraction of the window size and stretched to fit
+    pub render_scale: f32,
+    /// Vertical field of view of the voxel camera in degrees
+    pub fov: f32,
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        VideoSettings {
+            display_mode: DisplayMode::Windowed,
+            vsync: true,
+            render_scale: 1.,
+            // the bevy default of 45 degrees
+            fov: 45.,
+        }
+    }
+}
+
+impl VideoSettings {
+    pub const MIN_RENDER_SCALE: f32 = 0.25;
+    pub const MIN_FOV: f32 = 30.;
+    pub const MAX_FOV: f32 = 110.;
+
+    pub fn adjust_render_scale(&mut self, amount: f32) {
+        self.render_scale =
+            ((self.render_scale + amount).clamp(Self::MIN_RENDER_SCALE, 1.) * 100.).round() / 100.;
+    }
+
+    pub fn adjust_fov(&mut self, amount: f32) {
+        self.fov = (self.fov + amount).clamp(Self::MIN_FOV, Self::MAX_FOV);
+    }
+}
+
+fn apply_window_settings(
+    settings: Res<VideoSettings>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = window.get_single_mut() else {
+        warn!("Primary window not found for `apply_window_settings`!");
+        return;
+    };
+    window.mode = settings.display_mode.window_mode();
+    window.present_mode = if settings.vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    };
+}
+
+fn apply_camera_settings(
+    settings: Res<VideoSettings>,
+    mut cameras: Query<&mut Projection, With<VoxelPlayer>>,
+) {
+    for mut projection in &mut cameras {
+        if !settings.is_changed() && !projection.is_added() {
+            continue;
+        }
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov = settings.fov.to_radians();
+        }
+    }
+}
+
+/// The scaled down voxel world, shown behind the rest of the UI
+#[derive(Component)]
+struct ScaledView;
+
+/// Points the voxel camera at a smaller image when the render scale is below one
+fn apply_render_scale(
+    mut commands: Commands,
+    settings: Res<VideoSettings>,
+    mut resized: EventReader<WindowResized>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<&mut Camera, With<VoxelPlayer>>,
+    views: Query<Entity, With<ScaledView>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let resized = resized.read().count() > 0;
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    for mut camera in &mut cameras {
+        if !settings.is_changed() && !resized && !camera.is_added() {
+            continue;
+        }
+        for view in &views {
+            commands.entity(view).despawn_recursive();
+        }
+        if settings.render_scale >= 1. {
+            camera.target = RenderTarget::Window(WindowRef::Primary);
+            continue;
+        }
+        let size = Extent3d {
+            width: ((window.physical_width() as f32 * settings.render_scale) as u32).max(1),
+            height: ((window.physical_height() as f32 * settings.render_scale) as u32).max(1),
+            depth_or_array_layers: 1,
+        };
+        let mut image = Image::new_fill(
+            size,
+            TextureDimension::D2,
+            &[0, 0, 0, 255],
+            TextureFormat::Bgra8UnormSrgb,
+            default(),
+        );
+        image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+            | TextureUsages::COPY_DST
+            | TextureUsages::RENDER_ATTACHMENT;
+        let image = images.add(image);
+        camera.target = RenderTarget::Image(image.clone());
+        commands.spawn((
+            Name::new("Scaled View"),
+            ScaledView,
+            StateScoped(Screen::VoxelWorld),
+            ImageBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    ..default()
+                },
+                image: UiImage::new(image),
+                z_index: ZIndex::Global(-1),
+                ..default()
+            },
+        ));
+    }
+}
@@ -6,7 +6,6 @@ mod ui;
 
 use bevy::{
     asset::{io::AssetSource, AssetMetaCheck},
-    audio::{AudioPlugin, Volume},
     prelude::*,
 };
 use screen::voxel_world;
@@ -50,12 +49,6 @@ impl Plugin for AppPlugin {
                     .into(),
                     ..default()
                 })
-                .set(AudioPlugin {
-                    global_volume: GlobalVolume {
-                        volume: Volume::new(0.3),
-                    },
-                    ..default()
-                })
                 .set(ImagePlugin::default_nearest()),
         );
 
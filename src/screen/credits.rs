@@ -4,7 +4,7 @@ use bevy::prelude::*;
 
 use super::Screen;
 use crate::{
-    game::{assets::SoundtrackKey, audio::soundtrack::PlaySoundtrack},
+    game::audio::soundtrack::{PlaySoundtrack, SoundtrackContext},
     ui::prelude::*,
 };
 
@@ -45,10 +45,10 @@ fn enter_credits(mut commands: Commands) {
             children.label("Music - CC BY 3.0 by Kevin MacLeod");
             children.label("Effects - CC0 by Kenney");
 
-            children.button("Back").insert(CreditsAction::Back);
+            children.button("Back").insert((CreditsAction::Back, BackButton));
         });
 
-    commands.trigger(PlaySoundtrack::Key(SoundtrackKey::Credits));
+    commands.trigger(PlaySoundtrack::Context(SoundtrackContext::Credits));
 }
 
 fn exit_credits(mut commands: Commands) {
@@ -7,10 +7,14 @@ mod hex_util;
 pub mod movement;
 pub mod spawn;
 
-use crate::game::{assets::SoundtrackKey, audio::soundtrack::PlaySoundtrack};
+use crate::game::{
+    action_just_pressed,
+    audio::soundtrack::{PlaySoundtrack, SoundtrackContext},
+    PlayerAction,
+};
 
 use super::Screen;
-use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy::prelude::*;
 use cells::CellIcons;
 use hex_util::{go_to_voxel, spawn_hex_grid};
 use spawn::player::SpawnPlayer;
@@ -23,8 +27,9 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_systems(
         Update,
-        return_to_title_screen
-            .run_if(in_state(Screen::HexMap).and_then(input_just_pressed(KeyCode::Escape))),
+        return_to_title_screen.run_if(
+            in_state(Screen::HexMap).and_then(action_just_pressed(PlayerAction::ExitChunk)),
+        ),
     );
 
     app.add_plugins(cursor::CursorPlugin)
@@ -37,7 +42,7 @@ pub(super) fn plugin(app: &mut App) {
 
 fn enter_playing(mut commands: Commands) {
     commands.trigger(SpawnPlayer);
-    commands.trigger(PlaySoundtrack::Key(SoundtrackKey::Gameplay));
+    commands.trigger(PlaySoundtrack::Context(SoundtrackContext::HexMap));
 }
 
 fn exit_playing(mut commands: Commands) {
@@ -6,7 +6,7 @@ use std::{
 };
 
 use bevy::{
-    math::{IVec2, Quat, Vec3},
+    math::{IVec2, IVec3, Quat, Vec3},
     prelude::Component,
     reflect::Reflect,
 };
@@ -187,6 +187,11 @@ impl MapDirection {
             MapDirection::East => Quat::from_rotation_z(PI / 2.),
         }
     }
+
+    /// the neighbouring voxel a block placed with this direction faces
+    pub fn to_ivec3(self) -> IVec3 {
+        (self.to_rotation() * Vec3::Y).round().as_ivec3()
+    }
 }
 
 const TWO_THIRDS_PI: f32 = PI * 2.0 / 3.0;
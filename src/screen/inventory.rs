@@ -1,7 +1,6 @@
 use bevy::{
-    input::ButtonInput,
     log::warn,
-    prelude::{info, Component, KeyCode, Query, Res, With},
+    prelude::{info, Component, Query, With},
     reflect::Reflect,
 };
 use leafwing_input_manager::prelude::ActionState;
@@ -53,32 +52,54 @@ impl Inventory {
         }
     }
 
-    /// returns true if added and false if Inventory full
-    pub fn add_resource(&mut self, resource_type: BlockType, quantity: u32) -> bool {
-        // First, try to find a matching slot and add to it
+    /// Adds up to `max_stack` to each slot, filling stacks of the same resource before empty
+    /// slots. Returns how many did not fit, which the caller should drop rather than lose
+    pub fn add_resource(&mut self, resource_type: BlockType, quantity: u32, max_stack: u32) -> u32 {
+        let mut remaining = quantity;
+        // First, top up the slots that already hold it
         for slot in &mut self.slots {
-            if let Some(rt) = &slot.resource_type {
-                if *rt == resource_type {
-                    slot.quantity += quantity;
-                    return true;
-                }
+            if remaining == 0 {
+                break;
+            }
+            if slot.resource_type.as_ref() == Some(&resource_type) {
+                let added = remaining.min(max_stack.saturating_sub(slot.quantity));
+                slot.quantity += added;
+                remaining -= added;
             }
         }
 
-        // If no matching slot found, find the first empty slot
-        if let Some(empty_slot) = self
-            .slots
-            .iter_mut()
-            .find(|slot| slot.resource_type.is_none())
-        {
-            empty_slot.resource_type = Some(resource_type);
-            empty_slot.quantity = quantity;
-            return true;
+        // Then spill the rest into empty slots
+        for slot in &mut self.slots {
+            if remaining == 0 {
+                break;
+            }
+            if slot.resource_type.is_none() {
+                let added = remaining.min(max_stack);
+                slot.resource_type = Some(resource_type.clone());
+                slot.quantity = added;
+                remaining -= added;
+            }
         }
 
-        // If no empty slot found, the inventory is full
-        info!("Inventory full, couldn't add resource");
-        false
+        if remaining > 0 {
+            info!(
+                "Inventory full, {} {:?} did not fit",
+                remaining, resource_type
+            );
+        }
+        remaining
+    }
+
+    /// How many more of `resource_type` fit in the inventory
+    pub fn room_for(&self, resource_type: &BlockType, max_stack: u32) -> u32 {
+        self.slots
+            .iter()
+            .map(|slot| match &slot.resource_type {
+                None => max_stack,
+                Some(held) if held == resource_type => max_stack.saturating_sub(slot.quantity),
+                Some(_) => 0,
+            })
+            .sum()
     }
 
     pub fn get_total_resource(&self, resource_type: BlockType) -> u32 {
@@ -132,13 +153,129 @@ impl Inventory {
     pub fn get_selected_block(&self) -> Option<BlockType> {
         self.slots[self.selected_slot].resource_type.clone()
     }
+
+    /// Moves the stack in `from` onto `to`, joining them up to the stack size if they hold the
+    /// same resource and swapping them otherwise
+    pub fn move_slot(&mut self, from: usize, to: usize, max_stack: impl Fn(&BlockType) -> u32) {
+        if from == to || from >= self.slots.len() || to >= self.slots.len() {
+            return;
+        }
+        match self.slots[from].resource_type.clone() {
+            Some(resource_type)
+                if self.slots[to].resource_type.as_ref() == Some(&resource_type) =>
+            {
+                let moved = self.slots[from]
+                    .quantity
+                    .min(max_stack(&resource_type).saturating_sub(self.slots[to].quantity));
+                self.slots[to].quantity += moved;
+                self.slots[from].quantity -= moved;
+                if self.slots[from].quantity == 0 {
+                    self.slots[from].resource_type = None;
+                }
+            }
+            _ => self.slots.swap(from, to),
+        }
+    }
+
+    /// Moves half of the stack in `index` into the first empty slot, returning that slot
+    pub fn split_slot(&mut self, index: usize) -> Option<usize> {
+        let slot = self.slots.get(index)?;
+        if slot.quantity < 2 {
+            return None;
+        }
+        let resource_type = slot.resource_type.clone();
+        let empty = self
+            .slots
+            .iter()
+            .position(|slot| slot.resource_type.is_none())?;
+        let half = self.slots[index].quantity / 2;
+        self.slots[index].quantity -= half;
+        self.slots[empty] = InventorySlot {
+            resource_type,
+            quantity: half,
+        };
+        Some(empty)
+    }
+
+    /// Moves the stack in `index` between the hotbar row and the rest of the inventory, onto
+    /// stacks of the same resource with room and then empty slots. Returns false if none of it
+    /// could move
+    pub fn quick_move(&mut self, index: usize, max_stack: impl Fn(&BlockType) -> u32) -> bool {
+        let Some(resource_type) = self
+            .slots
+            .get(index)
+            .and_then(|slot| slot.resource_type.clone())
+        else {
+            return false;
+        };
+        let start = self.slots[index].quantity;
+        let limit = max_stack(&resource_type);
+        let hotbar = self.selected_row * 10..self.selected_row * 10 + 10;
+        let from_hotbar = hotbar.contains(&index);
+        let targets = (0..self.slots.len())
+            .filter(|target| hotbar.contains(target) != from_hotbar)
+            .collect::<Vec<_>>();
+        let same = targets.iter().filter(|target| {
+            let slot = &self.slots[**target];
+            slot.resource_type.as_ref() == Some(&resource_type) && slot.quantity < limit
+        });
+        let empty = targets
+            .iter()
+            .filter(|target| self.slots[**target].resource_type.is_none());
+        for target in same.chain(empty).copied().collect::<Vec<_>>() {
+            if self.slots[index].resource_type.is_none() {
+                break;
+            }
+            self.move_slot(index, target, &max_stack);
+        }
+        self.slots[index].resource_type.is_none() || self.slots[index].quantity < start
+    }
+
+    /// Joins stacks of the same resource up to their stack size and orders them by name, with
+    /// the empty slots last
+    pub fn sort(&mut self, max_stack: impl Fn(&BlockType) -> u32) {
+        let mut totals: Vec<(BlockType, u32)> = Vec::new();
+        for slot in &self.slots {
+            let Some(resource_type) = &slot.resource_type else {
+                continue;
+            };
+            match totals.iter_mut().find(|(held, _)| held == resource_type) {
+                Some((_, total)) => *total += slot.quantity,
+                None => totals.push((resource_type.clone(), slot.quantity)),
+            }
+        }
+        let mut stacks: Vec<InventorySlot> = Vec::new();
+        for (resource_type, mut total) in totals {
+            let limit = max_stack(&resource_type).max(1);
+            while total > 0 {
+                let quantity = total.min(limit);
+                stacks.push(InventorySlot {
+                    resource_type: Some(resource_type.clone()),
+                    quantity,
+                });
+                total -= quantity;
+            }
+        }
+        stacks.sort_by_cached_key(|stack| format!("{:?}", stack.resource_type));
+        stacks.resize(
+            self.slots.len(),
+            InventorySlot {
+                resource_type: None,
+                quantity: 0,
+            },
+        );
+        self.slots = stacks;
+    }
 }
 
 pub fn clear_inventory(
-    input: Res<ButtonInput<KeyCode>>,
+    input: Query<&ActionState<PlayerAction>, With<Player>>,
     mut player_inventory: Query<&mut Inventory, With<Player>>,
 ) {
-    if input.just_pressed(KeyCode::NumpadSubtract) {
+    let Ok(input) = input.get_single() else {
+        return;
+    };
+    if input.just_pressed(&PlayerAction::ClearInventory) {
         for mut inventory in &mut player_inventory {
             inventory.clear()
         }
@@ -146,58 +283,23 @@ pub fn clear_inventory(
 }
 
 pub fn change_row_inventory(
-    input: Res<ButtonInput<KeyCode>>,
     player: Query<&ActionState<PlayerAction>>,
     mut player_inventory: Query<&mut Inventory, With<Player>>,
 ) {
-    if input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
-        for key in input.get_just_pressed() {
-            match key {
-                KeyCode::Digit1 => {
-                    if let Ok(mut inventory) = player_inventory.get_single_mut() {
-                        inventory.selected_row = 0;
-                        inventory.selected_slot = 0;
-                    }
-                }
-                KeyCode::Digit2 => {
-                    if let Ok(mut inventory) = player_inventory.get_single_mut() {
-                        inventory.selected_row = 1;
-                        inventory.selected_slot = 10;
-                    }
-                }
-                KeyCode::Digit3 => {
-                    if let Ok(mut inventory) = player_inventory.get_single_mut() {
-                        inventory.selected_row = 2;
-                        inventory.selected_slot = 20;
-                    }
-                }
-                KeyCode::Digit4 => {
-                    if let Ok(mut inventory) = player_inventory.get_single_mut() {
-                        inventory.selected_row = 3;
-                        inventory.selected_slot = 30;
-                    }
-                }
-                KeyCode::Digit5 => {
-                    if let Ok(mut inventory) = player_inventory.get_single_mut() {
-                        inventory.selected_row = 4;
-                        inventory.selected_slot = 40;
-                    }
-                }
-                KeyCode::Digit6 => {
-                    if let Ok(mut inventory) = player_inventory.get_single_mut() {
-                        inventory.selected_row = 5;
-                        inventory.selected_slot = 50;
-                    }
-                }
-                _ => {}
-            }
-        }
-    }
     let Ok(input) = player.get_single() else {
         warn!("Player not loaded");
         return;
     };
 
+    for (row, action) in PlayerAction::ROWS.iter().enumerate() {
+        if input.just_pressed(action) {
+            if let Ok(mut inventory) = player_inventory.get_single_mut() {
+                inventory.selected_row = row;
+                inventory.selected_slot = row * 10;
+            }
+        }
+    }
+
     if input.just_pressed(&PlayerAction::ToolbarNext) {
         inc(&mut player_inventory)
     }
@@ -220,3 +322,106 @@ fn dec(player_inventory: &mut Query<&mut Inventory, With<Player>>) {
         inventory.selected_slot = inventory.selected_row * 10;
     }
 }
+
+#[cfg(test)]
+fn stacks_of(size: u32) -> impl Fn(&BlockType) -> u32 {
+    move |_| size
+}
+
+#[test]
+fn adding_fills_stacks_then_spills_into_empty_slots() {
+    let mut inventory = Inventory::new(3);
+    assert_eq!(inventory.add_resource(BlockType::Stone, 5, 4), 0);
+    assert_eq!(inventory.slots[0].quantity, 4);
+    assert_eq!(inventory.slots[1].quantity, 1);
+    assert_eq!(inventory.room_for(&BlockType::Stone, 4), 7);
+    // what does not fit is handed back
+    assert_eq!(inventory.add_resource(BlockType::Stone, 10, 4), 3);
+    assert!(inventory.slots.iter().all(|slot| slot.quantity == 4));
+    assert_eq!(inventory.room_for(&BlockType::Stone, 4), 0);
+}
+
+#[test]
+fn moving_a_slot_joins_or_swaps_stacks() {
+    let mut inventory = Inventory::new(3);
+    inventory.slots[0] = InventorySlot {
+        resource_type: Some(BlockType::Stone),
+        quantity: 4,
+    };
+    inventory.slots[1] = InventorySlot {
+        resource_type: Some(BlockType::Stone),
+        quantity: 2,
+    };
+    inventory.move_slot(0, 1, stacks_of(64));
+    assert_eq!(inventory.slots[0].resource_type, None);
+    assert_eq!(inventory.slots[1].quantity, 6);
+
+    inventory.move_slot(1, 2, stacks_of(64));
+    assert_eq!(inventory.slots[1].resource_type, None);
+    assert_eq!(inventory.slots[2].resource_type, Some(BlockType::Stone));
+    assert_eq!(inventory.slots[2].quantity, 6);
+}
+
+#[test]
+fn joining_stops_at_the_stack_size() {
+    let mut inventory = Inventory::new(2);
+    inventory.add_resource(BlockType::Stone, 6, 64);
+    inventory.slots[1] = InventorySlot {
+        resource_type: Some(BlockType::Stone),
+        quantity: 3,
+    };
+    inventory.move_slot(0, 1, stacks_of(5));
+    assert_eq!(inventory.slots[0].quantity, 4);
+    assert_eq!(inventory.slots[1].quantity, 5);
+}
+
+#[test]
+fn splitting_moves_half_into_an_empty_slot() {
+    let mut inventory = Inventory::new(3);
+    inventory.add_resource(BlockType::Stone, 5, 64);
+    assert_eq!(inventory.split_slot(0), Some(1));
+    assert_eq!(inventory.slots[0].quantity, 3);
+    assert_eq!(inventory.slots[1].resource_type, Some(BlockType::Stone));
+    assert_eq!(inventory.slots[1].quantity, 2);
+    // single items can not be split
+    inventory.slots[1].quantity = 1;
+    assert_eq!(inventory.split_slot(1), None);
+}
+
+#[test]
+fn quick_move_goes_between_the_hotbar_and_the_rest() {
+    let mut inventory = Inventory::new(20);
+    inventory.slots[12] = InventorySlot {
+        resource_type: Some(BlockType::Stone),
+        quantity: 3,
+    };
+    assert!(inventory.quick_move(12, stacks_of(64)));
+    assert_eq!(inventory.slots[0].quantity, 3);
+    assert!(inventory.quick_move(0, stacks_of(64)));
+    assert_eq!(inventory.slots[10].quantity, 3);
+    assert_eq!(inventory.slots[0].resource_type, None);
+}
+
+#[test]
+fn sorting_joins_stacks_and_leaves_empty_slots_last() {
+    let mut inventory = Inventory::new(4);
+    inventory.slots[1] = InventorySlot {
+        resource_type: Some(BlockType::Stone),
+        quantity: 2,
+    };
+    inventory.slots[3] = InventorySlot {
+        resource_type: Some(BlockType::Stone),
+        quantity: 5,
+    };
+    inventory.sort(stacks_of(64));
+    assert_eq!(inventory.slots[0].resource_type, Some(BlockType::Stone));
+    assert_eq!(inventory.slots[0].quantity, 7);
+    assert!(inventory.slots[1..]
+        .iter()
+        .all(|slot| slot.resource_type.is_none()));
+
+    // stacks over the size are split back up
+    inventory.sort(stacks_of(4));
+    assert_eq!(inventory.slots[0].quantity, 4);
+    assert_eq!(inventory.slots[1].quantity, 3);
+}
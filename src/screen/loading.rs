@@ -3,7 +3,7 @@
 
 use bevy::prelude::*;
 
-use super::Screen;
+use super::{progression::Goals, Screen};
 use crate::{
     game::assets::{HandleMap, ImageKey, SfxKey, SoundtrackKey},
     ui::widgets::Containers,
@@ -26,10 +26,12 @@ fn all_assets_loaded(
     image_handles: Res<HandleMap<ImageKey>>,
     sfx_handles: Res<HandleMap<SfxKey>>,
     soundtrack_handles: Res<HandleMap<SoundtrackKey>>,
+    goals: Res<Goals>,
 ) -> bool {
     image_handles.all_loaded(&asset_server)
         && sfx_handles.all_loaded(&asset_server)
         && soundtrack_handles.all_loaded(&asset_server)
+        && asset_server.is_loaded_with_dependencies(&goals.0)
 }
 
 fn continue_to_title(mut next_screen: ResMut<NextState<Screen>>) {
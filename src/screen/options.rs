@@ -3,21 +3,29 @@ use bevy::{
     prelude::*,
 };
 use leafwing_input_manager::prelude::{DualAxis, InputMap, UserInput};
+use strum::IntoEnumIterator;
 
 use crate::{
     game::{
         assets::{HandleMap, ImageKey},
-        main_character::Player,
+        audio::{AudioSettings, VolumeBus},
+        main_character::{binding_conflicts, reset_device_bindings, InputDevice, Player},
+        video::VideoSettings,
         PlayerAction,
     },
     ui::{
         icons::KeyIcons,
-        prelude::InteractionQuery,
-        widgets::{Containers, UiIcon, Widgets},
+        navigation::UiFocus,
+        prelude::{BackButton, InteractionQuery},
+        widgets::{Containers, Widgets},
     },
 };
 
-use super::{inventory::Inventory, Menu, Screen};
+use super::{
+    inventory::Inventory,
+    voxel_world::{player_controller::VoxelSettings, world::loot::DropSettings},
+    Menu, Screen,
+};
 
 pub fn plugin(app: &mut App) {
     app.add_systems(
@@ -29,6 +37,26 @@ pub fn plugin(app: &mut App) {
         spawn_keybind_menu,
     )
     .add_systems(OnEnter(Screen::Options(OptionMenus::Dev)), spawn_dev_menu)
+    .add_systems(
+        OnEnter(Screen::Options(OptionMenus::Audio)),
+        spawn_audio_menu,
+    )
+    .add_systems(
+        OnEnter(Screen::Options(OptionMenus::Video)),
+        spawn_video_menu,
+    )
+    .add_systems(
+        Update,
+        (run_video_actions, update_video_labels)
+            .chain()
+            .run_if(in_state(Screen::Options(OptionMenus::Video))),
+    )
+    .add_systems(
+        Update,
+        (run_audio_actions, update_volume_labels)
+            .chain()
+            .run_if(in_state(Screen::Options(OptionMenus::Audio))),
+    )
     .add_systems(
         Update,
         (handle_option_action, run_rebind_actions).run_if(in_state(Menu)),
@@ -37,6 +65,17 @@ pub fn plugin(app: &mut App) {
         Update,
         (update_new_binding).run_if(resource_changed::<RebindingState>),
     )
+    .add_systems(
+        Update,
+        (
+            run_keybind_actions,
+            (despawn_keybind_menu, spawn_keybind_menu)
+                .chain()
+                .run_if(bindings_changed),
+        )
+            .chain()
+            .run_if(in_state(Screen::Options(OptionMenus::KeyBinding))),
+    )
     .insert_resource(RebindingState {
         action: PlayerAction::Hit,
         old: BindingKey(None),
@@ -45,8 +84,11 @@ pub fn plugin(app: &mut App) {
     })
     .add_systems(
         Update,
-        find_keybind.run_if(in_state(Screen::Options(OptionMenus::Rebind))),
-    );
+        find_keybind
+            .before(run_rebind_actions)
+            .run_if(in_state(Screen::Options(OptionMenus::Rebind))),
+    )
+    .add_systems(OnExit(Screen::Options(OptionMenus::Rebind)), stop_rebinding);
 }
 
 fn spawn_select_menu(mut commands: Commands) {
@@ -56,7 +98,9 @@ fn spawn_select_menu(mut commands: Commands) {
         .with_children(|p| {
             p.button("Dev Tools").insert(OptionAction::OpenDev);
             p.button("Key Bindings").insert(OptionAction::OpenKeyBind);
-            p.button("Back").insert(OptionAction::Back);
+            p.button("Audio").insert(OptionAction::OpenAudio);
+            p.button("Video").insert(OptionAction::OpenVideo);
+            p.button("Back").insert((OptionAction::Back, BackButton));
         });
 }
 
@@ -67,10 +111,231 @@ fn spawn_dev_menu(mut commands: Commands) {
         .with_children(|p| {
             p.button("Clear Inventory")
                 .insert(OptionAction::ClearInventory);
-            p.button("Back").insert(OptionAction::Back);
+            p.button("Back").insert((OptionAction::Back, BackButton));
+        });
+}
+
+fn spawn_audio_menu(mut commands: Commands, settings: Res<AudioSettings>) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Options(OptionMenus::Audio)))
+        .with_children(|p| {
+            for bus in [VolumeBus::Master, VolumeBus::Music, VolumeBus::Sfx] {
+                p.stepper(
+                    volume_text(bus, &settings),
+                    AudioAction::Lower(bus),
+                    AudioAction::Raise(bus),
+                    VolumeLabel(bus),
+                );
+            }
+            p.button(mute_text(&settings))
+                .insert(AudioAction::ToggleMute);
+            p.button("Back").insert((OptionAction::Back, BackButton));
+        });
+}
+
+fn volume_text(bus: VolumeBus, settings: &AudioSettings) -> String {
+    format!("{:?}: {:.0}%", bus, settings.get(bus) * 100.)
+}
+
+fn mute_text(settings: &AudioSettings) -> &'static str {
+    if settings.muted {
+        "Unmute"
+    } else {
+        "Mute"
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+enum AudioAction {
+    Lower(VolumeBus),
+    Raise(VolumeBus),
+    ToggleMute,
+}
+
+/// The label showing the value of a volume slider
+#[derive(Component)]
+struct VolumeLabel(VolumeBus);
+
+fn run_audio_actions(
+    button_query: InteractionQuery<&AudioAction>,
+    mut settings: ResMut<AudioSettings>,
+) {
+    for (interaction, action) in &button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match *action {
+                AudioAction::Lower(bus) => settings.adjust(bus, -AudioSettings::STEP),
+                AudioAction::Raise(bus) => settings.adjust(bus, AudioSettings::STEP),
+                AudioAction::ToggleMute => settings.muted = !settings.muted,
+            }
+        }
+    }
+}
+
+fn update_volume_labels(
+    settings: Res<AudioSettings>,
+    labels: Query<(&VolumeLabel, &Children)>,
+    mute: Query<(&AudioAction, &Children)>,
+    mut text: Query<&mut Text>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let values = labels
+        .iter()
+        .map(|(label, children)| (volume_text(label.0, &settings), children))
+        .chain(
+            mute.iter()
+                .filter(|(action, _)| matches!(action, AudioAction::ToggleMute))
+                .map(|(_, children)| (mute_text(&settings).to_string(), children)),
+        );
+    for (value, children) in values {
+        set_child_text(children, &mut text, &value);
+    }
+}
+
+/// Buttons and labels keep their text on a child
+fn set_child_text(children: &Children, text: &mut Query<&mut Text>, value: &str) {
+    for child in children {
+        if let Ok(mut text) = text.get_mut(*child) {
+            if text.sections[0].value != value {
+                text.sections[0].value = value.to_string();
+            }
+        }
+    }
+}
+
+fn spawn_video_menu(
+    mut commands: Commands,
+    video: Res<VideoSettings>,
+    controls: Res<VoxelSettings>,
+    drops: Res<DropSettings>,
+) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Options(OptionMenus::Video)))
+        .with_children(|p| {
+            for field in [VideoField::DisplayMode, VideoField::Vsync] {
+                p.button(video_text(field, &video, &controls, &drops))
+                    .insert((VideoAction::Toggle(field), VideoLabel(field)));
+            }
+            for field in [
+                VideoField::RenderScale,
+                VideoField::Fov,
+                VideoField::Sensitivity,
+            ] {
+                p.stepper(
+                    video_text(field, &video, &controls, &drops),
+                    VideoAction::Lower(field),
+                    VideoAction::Raise(field),
+                    VideoLabel(field),
+                );
+            }
+            for field in [VideoField::InvertY, VideoField::DropItems] {
+                p.button(video_text(field, &video, &controls, &drops))
+                    .insert((VideoAction::Toggle(field), VideoLabel(field)));
+            }
+            p.button("Back").insert((OptionAction::Back, BackButton));
         });
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VideoField {
+    DisplayMode,
+    Vsync,
+    RenderScale,
+    Fov,
+    Sensitivity,
+    InvertY,
+    /// whether broken blocks drop into the world instead of going into the inventory
+    DropItems,
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+fn video_text(
+    field: VideoField,
+    video: &VideoSettings,
+    controls: &VoxelSettings,
+    drops: &DropSettings,
+) -> String {
+    match field {
+        VideoField::DisplayMode => format!("{:?}", video.display_mode),
+        VideoField::Vsync => format!("VSync {}", on_off(video.vsync)),
+        VideoField::RenderScale => format!("Render Scale: {:.0}%", video.render_scale * 100.),
+        VideoField::Fov => format!("FOV: {:.0}", video.fov),
+        VideoField::Sensitivity => format!(
+            "Sensitivity: {:.1}x",
+            controls.mouse_sensitivity / VoxelSettings::DEFAULT_SENSITIVITY
+        ),
+        VideoField::InvertY => format!("Invert Y {}", on_off(controls.invert_y)),
+        VideoField::DropItems => format!("Drop Blocks As Items {}", on_off(drops.as_items)),
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+enum VideoAction {
+    Lower(VideoField),
+    Raise(VideoField),
+    Toggle(VideoField),
+}
+
+/// The label or button showing the value of a video or control setting
+#[derive(Component)]
+struct VideoLabel(VideoField);
+
+fn run_video_actions(
+    button_query: InteractionQuery<&VideoAction>,
+    mut video: ResMut<VideoSettings>,
+    mut controls: ResMut<VoxelSettings>,
+    mut drops: ResMut<DropSettings>,
+) {
+    for (interaction, action) in &button_query {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        let (field, sign) = match *action {
+            VideoAction::Lower(field) => (field, -1.),
+            VideoAction::Raise(field) => (field, 1.),
+            VideoAction::Toggle(field) => (field, 0.),
+        };
+        match field {
+            VideoField::DisplayMode => video.display_mode = video.display_mode.next(),
+            VideoField::Vsync => video.vsync = !video.vsync,
+            VideoField::RenderScale => video.adjust_render_scale(sign * 0.25),
+            VideoField::Fov => video.adjust_fov(sign * 5.),
+            VideoField::Sensitivity => controls.adjust_sensitivity(sign * 0.1),
+            VideoField::InvertY => controls.invert_y = !controls.invert_y,
+            VideoField::DropItems => drops.as_items = !drops.as_items,
+        }
+    }
+}
+
+fn update_video_labels(
+    video: Res<VideoSettings>,
+    controls: Res<VoxelSettings>,
+    drops: Res<DropSettings>,
+    labels: Query<(&VideoLabel, &Children)>,
+    mut text: Query<&mut Text>,
+) {
+    if !video.is_changed() && !controls.is_changed() && !drops.is_changed() {
+        return;
+    }
+    for (label, children) in &labels {
+        set_child_text(
+            children,
+            &mut text,
+            &video_text(label.0, &video, &controls, &drops),
+        );
+    }
+}
+
 fn spawn_keybind_menu(
     mut commands: Commands,
     layout: Res<crate::game::assets::ButtonLayout>,
@@ -84,22 +349,114 @@ fn spawn_keybind_menu(
     commands
         .ui_root()
         .insert(StateScoped(Screen::Options(OptionMenus::KeyBinding)))
+        .insert(KeybindMenu)
         .with_children(|p| {
             if let Some(icons) = icons.get(&ImageKey::ButtonIcons) {
                 p.key_bindings(&layout.0, binding, icons);
             } else {
                 error!("Failed to get image");
             }
-            p.button("Back").insert(OptionAction::Back);
+            for (input, actions) in binding_conflicts(binding) {
+                p.label(conflict_text(&input, &actions));
+            }
+            p.horizontal().with_children(|p| {
+                p.button("Reset Keys")
+                    .insert(KeybindAction::Reset(InputDevice::KeyboardMouse));
+                p.button("Reset Pad")
+                    .insert(KeybindAction::Reset(InputDevice::Gamepad));
+                #[cfg(not(target_family = "wasm"))]
+                {
+                    p.button("Export").insert(KeybindAction::Export);
+                    p.button("Import").insert(KeybindAction::Import);
+                }
+            });
+            p.button("Back").insert((OptionAction::Back, BackButton));
         });
 }
 
+fn conflict_text(input: &UserInput, actions: &[PlayerAction]) -> String {
+    format!(
+        "{} is bound to {:?}",
+        KeyIcons::from(input.clone()).label(),
+        actions
+    )
+}
+
+/// The root of the key binding menu, rebuilt when the bindings change
+#[derive(Component)]
+struct KeybindMenu;
+
+/// The file bindings are exported to and imported from, next to the game
+#[cfg(not(target_family = "wasm"))]
+const KEYBIND_FILE: &str = "keybinds.ron";
+
+#[derive(Component, Clone, Copy)]
+enum KeybindAction {
+    Reset(InputDevice),
+    #[cfg(not(target_family = "wasm"))]
+    Export,
+    #[cfg(not(target_family = "wasm"))]
+    Import,
+}
+
+fn run_keybind_actions(
+    button_query: InteractionQuery<&KeybindAction>,
+    mut bindings: Query<&mut InputMap<PlayerAction>, With<Player>>,
+) {
+    for (interaction, action) in &button_query {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        for mut bindings in &mut bindings {
+            match *action {
+                KeybindAction::Reset(device) => reset_device_bindings(&mut bindings, device),
+                #[cfg(not(target_family = "wasm"))]
+                KeybindAction::Export => {
+                    match ron::ser::to_string_pretty(&*bindings, Default::default()) {
+                        Ok(file) => {
+                            if let Err(e) = std::fs::write(KEYBIND_FILE, file) {
+                                error!("Failed to export keybinds {e}");
+                            } else {
+                                info!("Exported keybinds to {KEYBIND_FILE}");
+                            }
+                        }
+                        Err(e) => error!("Failed to export keybinds {e}"),
+                    }
+                }
+                #[cfg(not(target_family = "wasm"))]
+                KeybindAction::Import => {
+                    let imported = std::fs::read_to_string(KEYBIND_FILE)
+                        .map_err(|e| e.to_string())
+                        .and_then(|file| ron::from_str(&file).map_err(|e| e.to_string()));
+                    match imported {
+                        Ok(imported) => *bindings = imported,
+                        Err(e) => error!("Failed to import keybinds from {KEYBIND_FILE} {e}"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn bindings_changed(changed: Query<(), Changed<InputMap<PlayerAction>>>) -> bool {
+    !changed.is_empty()
+}
+
+/// Cleared before [`spawn_keybind_menu`] builds it again with the new bindings
+fn despawn_keybind_menu(mut commands: Commands, menus: Query<Entity, With<KeybindMenu>>) {
+    for menu in &menus {
+        commands.entity(menu).despawn_recursive();
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum OptionMenus {
     Select,
     KeyBinding,
     Dev,
     Rebind,
+    Audio,
+    Video,
 }
 
 impl OptionMenus {
@@ -108,6 +465,8 @@ impl OptionMenus {
             OptionMenus::Select => Screen::Title,
             OptionMenus::KeyBinding => Screen::Options(OptionMenus::Select),
             OptionMenus::Dev => Screen::Options(OptionMenus::Select),
+            OptionMenus::Audio => Screen::Options(OptionMenus::Select),
+            OptionMenus::Video => Screen::Options(OptionMenus::Select),
             OptionMenus::Rebind => Screen::Options(OptionMenus::KeyBinding),
         }
     }
@@ -117,6 +476,8 @@ impl OptionMenus {
 enum OptionAction {
     OpenDev,
     OpenKeyBind,
+    OpenAudio,
+    OpenVideo,
     Back,
     ClearInventory,
 }
@@ -134,6 +495,8 @@ fn handle_option_action(
                 OptionAction::OpenKeyBind => {
                     next_screen.set(Screen::Options(OptionMenus::KeyBinding))
                 }
+                OptionAction::OpenAudio => next_screen.set(Screen::Options(OptionMenus::Audio)),
+                OptionAction::OpenVideo => next_screen.set(Screen::Options(OptionMenus::Video)),
                 OptionAction::Back => {
                     next_screen.set(if let Screen::Options(open) = current_screen.get() {
                         open.prev()
@@ -167,6 +530,7 @@ fn spawn_rebind_menu(
     commands: &mut Commands,
     action: PlayerAction,
     state: &RebindingState,
+    bindings: &InputMap<PlayerAction>,
     icons: &Handle<Image>,
     layout: &Handle<TextureAtlasLayout>,
 ) {
@@ -178,43 +542,82 @@ fn spawn_rebind_menu(
             p.horizontal().with_children(|p| {
                 if let Some(icon) = state.old.0.clone() {
                     p.label("Current Binding: ");
-                    p.icon(layout.clone(), icons.clone(), KeyIcons::from(icon));
+                    p.key_icon(layout, icons, &KeyIcons::from(icon));
                 } else {
                     p.label("Unbound");
                 }
             });
             p.horizontal().with_children(|p| {
                 p.label("New Binding: ");
-                if let Some(icon) = state.new.0.clone() {
-                    p.icon(layout.clone(), icons.clone(), KeyIcons::from(icon))
-                        .insert(NewBinding);
-                } else {
-                    p.icon(layout.clone(), icons.clone(), KeyIcons::NotSupported)
-                        .insert((NewBinding, Visibility::Hidden));
-                }
+                p.horizontal().insert(NewBinding).with_children(|p| {
+                    if let Some(icon) = state.new.0.clone() {
+                        p.key_icon(layout, icons, &KeyIcons::from(icon));
+                    }
+                });
                 p.button("Set").insert(RebindAction::Start);
             });
+            p.label(conflict_warning(state, bindings))
+                .insert(ConflictWarning);
             p.button("Apply").insert(RebindAction::Apply);
             p.button("Clear").insert(RebindAction::Delete);
-            p.button("Close").insert(OptionAction::Back);
+            p.button("Close").insert((OptionAction::Back, BackButton));
         });
 }
 
+/// Holds the icon of the new binding, rebuilt when it changes
 #[derive(Component)]
 struct NewBinding;
 
+/// Warns when the new binding is already used by another action
+#[derive(Component)]
+struct ConflictWarning;
+
+fn conflict_warning(state: &RebindingState, bindings: &InputMap<PlayerAction>) -> String {
+    let Some(new) = &state.new.0 else {
+        return String::new();
+    };
+    let others = PlayerAction::iter()
+        .filter(|action| *action != state.action)
+        .filter(|action| {
+            bindings
+                .get(action)
+                .is_some_and(|bound| bound.contains(new))
+        })
+        .collect::<Vec<_>>();
+    if others.is_empty() {
+        String::new()
+    } else {
+        format!("Also bound to {:?}", others)
+    }
+}
+
 fn update_new_binding(
+    mut commands: Commands,
     state: Res<RebindingState>,
-    mut new: Query<(&mut TextureAtlas, &mut Visibility), With<NewBinding>>,
+    new: Query<Entity, With<NewBinding>>,
+    layout: Res<crate::game::assets::ButtonLayout>,
+    icons: Res<HandleMap<ImageKey>>,
+    warnings: Query<&Children, With<ConflictWarning>>,
+    bindings: Query<&InputMap<PlayerAction>, With<Player>>,
+    mut text: Query<&mut Text>,
 ) {
-    for (mut atlas, mut vis) in &mut new {
-        println!("Info");
+    if let Ok(bindings) = bindings.get_single() {
+        let warning = conflict_warning(&state, bindings);
+        for children in &warnings {
+            set_child_text(children, &mut text, &warning);
+        }
+    }
+    let Some(icons) = icons.get(&ImageKey::ButtonIcons) else {
+        error!("Button Icons Not loaded");
+        return;
+    };
+    for container in &new {
+        let mut container = commands.entity(container);
+        container.despawn_descendants();
         if let Some(new) = &state.new.0 {
-            atlas.index = KeyIcons::from(new.clone()).index();
-            *vis = Visibility::Visible;
-        } else {
-            atlas.index = KeyIcons::NotSupported.index();
-            *vis = Visibility::Hidden;
+            container.with_children(|p| {
+                p.key_icon(&layout.0, icons, &KeyIcons::from(new.clone()));
+            });
         }
     }
 }
@@ -227,7 +630,8 @@ fn run_rebind_actions(
     mut state: ResMut<RebindingState>,
     layout: Res<crate::game::assets::ButtonLayout>,
     icons: Res<HandleMap<ImageKey>>,
-    mut bindings: Query<&mut InputMap<PlayerAction>>,
+    mut bindings: Query<&mut InputMap<PlayerAction>, With<Player>>,
+    mut focus: ResMut<UiFocus>,
 ) {
     let Some(icons) = icons.get(&ImageKey::ButtonIcons) else {
         error!("Button Icons Not loaded");
@@ -245,7 +649,11 @@ fn run_rebind_actions(
                     state.action = *player;
                     state.old = binding.clone();
                     state.new = binding.clone();
-                    spawn_rebind_menu(&mut commands, *player, &state, icons, &layout.0);
+                    let Ok(current) = bindings.get_single() else {
+                        error!("Player bindings not loaded");
+                        continue;
+                    };
+                    spawn_rebind_menu(&mut commands, *player, &state, current, icons, &layout.0);
                 }
                 RebindAction::Delete => state.new = BindingKey(None),
                 RebindAction::Apply => {
@@ -257,6 +665,8 @@ fn run_rebind_actions(
                             bindings.insert(state.action, new);
                         }
                     }
+                    // applying again should not remove the binding that was just added
+                    state.old = state.new.clone();
                 }
                 RebindAction::New => {
                     next_screen.set(Screen::Options(OptionMenus::Rebind));
@@ -267,9 +677,17 @@ fn run_rebind_actions(
                     state.action = *player;
                     state.old = BindingKey(None);
                     state.new = BindingKey(None);
-                    spawn_rebind_menu(&mut commands, *player, &state, icons, &layout.0);
+                    let Ok(current) = bindings.get_single() else {
+                        error!("Player bindings not loaded");
+                        continue;
+                    };
+                    spawn_rebind_menu(&mut commands, *player, &state, current, icons, &layout.0);
+                }
+                RebindAction::Start => {
+                    state.active = true;
+                    // the next press is the binding, not a menu button
+                    focus.paused = true;
                 }
-                RebindAction::Start => state.active = true,
             }
         }
     }
@@ -283,6 +701,11 @@ struct RebindingState {
     active: bool,
 }
 
+fn stop_rebinding(mut state: ResMut<RebindingState>, mut focus: ResMut<UiFocus>) {
+    state.active = false;
+    focus.paused = false;
+}
+
 #[derive(Component, Clone)]
 pub struct BindingKey(pub Option<UserInput>);
 
@@ -294,7 +717,10 @@ fn find_keybind(
     //mut mouse_motion: EventReader<MouseMotion>,
     gamepad_axis: Res<Axis<GamepadAxis>>,
     mouse: Res<ButtonInput<MouseButton>>,
+    mut focus: ResMut<UiFocus>,
 ) {
+    // navigation comes back the frame after the binding was found
+    focus.paused = state.active;
     if !state.active {
         return;
     }
@@ -0,0 +1,296 @@
+//! Delivery goals the player works through by feeding items into a score block.
+
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{assets::SfxKey, audio::sfx::PlaySfx};
+
+use super::{
+    voxel_world::{
+        ui::{spawn_hud_column, HudColumn},
+        voxels::BlockType,
+    },
+    Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<DeliveryGoals>()
+        .init_asset_loader::<DeliveryGoalsLoader>()
+        .init_resource::<Goals>()
+        .init_resource::<Score>()
+        .init_resource::<Target>()
+        .init_state::<GameProgress>();
+
+    app.add_systems(
+        OnEnter(Screen::VoxelWorld),
+        spawn_progress_panel.after(spawn_hud_column),
+    )
+    .add_systems(
+        Update,
+        (check_game_complete, update_progress_panel)
+            .chain()
+            .run_if(in_state(Screen::VoxelWorld)),
+    )
+    .add_systems(OnEnter(GameProgress::Complete), game_complete);
+}
+
+#[derive(Resource, Default, Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Score(pub i32);
+
+/// Resource for how far through the [`DeliveryGoals`] the player is
+#[derive(Resource, Default, Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Target {
+    pub stage: usize,
+    pub delivered: u32,
+}
+
+/// Set to complete once every goal has been delivered
+#[derive(States, Debug, Hash, PartialEq, Eq, Clone, Default)]
+pub enum GameProgress {
+    #[default]
+    Playing,
+    Complete,
+}
+
+/// The list of goals loaded from a `.goals` file
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct DeliveryGoals {
+    pub goals: Vec<DeliveryGoal>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DeliveryGoal {
+    pub item: BlockType,
+    #[serde(default = "one")]
+    pub quantity: u32,
+    #[serde(default)]
+    pub score: i32,
+    /// items given to the player when this goal is met
+    #[serde(default)]
+    pub rewards: Vec<(BlockType, u32)>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+#[derive(Resource)]
+pub struct Goals(pub Handle<DeliveryGoals>);
+
+impl FromWorld for Goals {
+    fn from_world(world: &mut World) -> Self {
+        Goals(world.resource::<AssetServer>().load("progression.goals"))
+    }
+}
+
+pub enum Delivery {
+    /// Not what the current goal wants
+    Rejected,
+    /// Counted towards the current goal
+    Counted,
+    /// The current goal is done, the player has earned its rewards
+    GoalMet(DeliveryGoal),
+}
+
+impl Target {
+    /// The goal being worked towards, None once every goal is done
+    pub fn goal<'a>(&self, goals: &'a DeliveryGoals) -> Option<&'a DeliveryGoal> {
+        goals.goals.get(self.stage)
+    }
+
+    pub fn is_complete(&self, goals: &DeliveryGoals) -> bool {
+        self.stage >= goals.goals.len()
+    }
+
+    pub fn deliver(&mut self, block: &BlockType, goals: &DeliveryGoals) -> Delivery {
+        let Some(goal) = self.goal(goals) else {
+            return Delivery::Rejected;
+        };
+        if &goal.item != block {
+            return Delivery::Rejected;
+        }
+        self.delivered += 1;
+        if self.delivered < goal.quantity {
+            return Delivery::Counted;
+        }
+        let goal = goal.clone();
+        self.stage += 1;
+        self.delivered = 0;
+        Delivery::GoalMet(goal)
+    }
+}
+
+struct DeliveryGoalsLoader;
+
+impl FromWorld for DeliveryGoalsLoader {
+    fn from_world(_world: &mut World) -> Self {
+        DeliveryGoalsLoader
+    }
+}
+
+impl AssetLoader for DeliveryGoalsLoader {
+    type Asset = DeliveryGoals;
+    type Settings = ();
+    type Error = GoalsLoadError;
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut bevy::asset::LoadContext,
+    ) -> impl bevy::utils::ConditionalSendFuture<Output = Result<Self::Asset, Self::Error>> {
+        async {
+            let mut str = String::default();
+            reader.read_to_string(&mut str).await?;
+            Ok(ron::from_str::<DeliveryGoals>(&str)?)
+        }
+    }
+    fn extensions(&self) -> &[&str] {
+        &["goals"]
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+enum GoalsLoadError {
+    #[error("Io Error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Ron Error: {0}")]
+    Ron(#[from] ron::de::SpannedError),
+}
+
+fn check_game_complete(
+    target: Res<Target>,
+    goals: Res<Goals>,
+    data: Res<Assets<DeliveryGoals>>,
+    progress: Res<State<GameProgress>>,
+    mut next_progress: ResMut<NextState<GameProgress>>,
+) {
+    let Some(goals) = data.get(goals.0.id()) else {
+        return;
+    };
+    let complete = target.is_complete(goals);
+    match progress.get() {
+        GameProgress::Playing if complete => next_progress.set(GameProgress::Complete),
+        GameProgress::Complete if !complete => next_progress.set(GameProgress::Playing),
+        _ => {}
+    }
+}
+
+fn game_complete(mut commands: Commands) {
+    info!("All deliveries complete");
+    commands.trigger(PlaySfx::Key(SfxKey::Progress));
+}
+
+#[derive(Component)]
+struct ProgressText;
+
+fn spawn_progress_panel(mut commands: Commands, column: Query<Entity, With<HudColumn>>) {
+    let Ok(column) = column.get_single() else {
+        return;
+    };
+    let panel = commands
+        .spawn((
+            Name::new("Progress Panel"),
+            NodeBundle {
+                style: Style {
+                    padding: UiRect::all(Val::Px(8.)),
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(Color::srgba(0.2, 0.2, 0.2, 0.8)),
+                ..Default::default()
+            },
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Name::new("Progress Text"),
+                ProgressText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        color: crate::ui::palette::LABEL_TEXT,
+                        ..default()
+                    },
+                ),
+            ));
+        })
+        .id();
+    // always at the top of the column, whatever else was put in it first
+    commands.entity(column).insert_children(0, &[panel]);
+}
+
+fn update_progress_panel(
+    score: Res<Score>,
+    target: Res<Target>,
+    goals: Res<Goals>,
+    data: Res<Assets<DeliveryGoals>>,
+    added: Query<(), Added<ProgressText>>,
+    mut texts: Query<&mut Text, With<ProgressText>>,
+) {
+    if added.is_empty() && !score.is_changed() && !target.is_changed() {
+        return;
+    }
+    let Some(goals) = data.get(goals.0.id()) else {
+        return;
+    };
+    let value = match target.goal(goals) {
+        Some(goal) => format!(
+            "Score: {}\nDeliver {:?}: {}/{}",
+            score.0, goal.item, target.delivered, goal.quantity
+        ),
+        None => format!("Score: {}\nAll deliveries complete!", score.0),
+    };
+    for mut text in &mut texts {
+        text.sections[0].value = value.clone();
+    }
+}
+
+#[test]
+fn every_goal_can_be_delivered_in_order() {
+    use super::voxel_world::world::multi_block::MultiBlocks;
+
+    // the same chain as `assets/progression.goals`, which is only checked out with git-lfs
+    let goals: DeliveryGoals = ron::from_str(
+        "(goals: [
+            (item: Stone, quantity: 1, score: 1),
+            (item: Coal, quantity: 1, score: 1),
+            (item: IronOre, quantity: 1, score: 1),
+            (item: Sand, quantity: 4, score: 1),
+            (item: IronBlock, quantity: 1, score: 1, rewards: [(IronPickaxe, 1)]),
+            (item: Glass, quantity: 4, score: 2),
+            (item: CopperBlock, quantity: 4, score: 2, rewards: [(CopperPickaxe, 1)]),
+            (item: CobaltBlock, quantity: 4, score: 3, rewards: [(CobaltPickaxe, 1)]),
+            (item: Magnesium, quantity: 8, score: 3),
+            (item: Sodium, quantity: 8, score: 3),
+            (item: Potassium, quantity: 8, score: 5),
+        ])",
+    )
+    .unwrap();
+    // machines give back their ingredients when broken, so they never become items
+    let recipes = MultiBlocks::from_world(&mut World::new());
+    let machines = recipes
+        .types()
+        .filter_map(|recipe| recipes.machine(recipe))
+        .collect::<Vec<_>>();
+    let mut target = Target::default();
+    for goal in goals.goals.iter() {
+        assert!(
+            !machines.contains(&goal.item),
+            "{:?} can not be held",
+            goal.item
+        );
+        for _ in 1..goal.quantity {
+            assert!(matches!(
+                target.deliver(&goal.item, &goals),
+                Delivery::Counted
+            ));
+        }
+        assert!(matches!(
+            target.deliver(&goal.item, &goals),
+            Delivery::GoalMet(_)
+        ));
+    }
+    assert!(target.is_complete(&goals));
+}
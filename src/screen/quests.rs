@@ -0,0 +1,379 @@
+//! Objectives that guide the player and unlock new machines.
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use leafwing_input_manager::prelude::ActionState;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    assets::SfxKey, audio::sfx::PlaySfx, main_character::Player, HexSelect, PlayerAction,
+};
+
+use super::{
+    inventory::Inventory,
+    voxel_world::{
+        item::DropItems,
+        voxel_util::WorldType,
+        voxels::{Block, BlockType, Blocks},
+        world::multi_block::MultiBlockType,
+    },
+    Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Quests>()
+        .init_resource::<QuestLog>()
+        .init_resource::<UnlockedRecipes>()
+        .observe(track_quests);
+
+    app.add_systems(OnEnter(Screen::VoxelWorld), (visit_hex, spawn_quest_log))
+        .add_systems(
+            Update,
+            (
+                update_unlocks.run_if(resource_changed::<QuestLog>),
+                toggle_quest_log,
+                update_quest_log,
+            )
+                .run_if(in_state(Screen::VoxelWorld)),
+        );
+}
+
+/// Trigger this event when the player does something a quest might care about
+#[derive(Event, Debug, Clone)]
+pub enum QuestEvent {
+    Mined(BlockType),
+    Built(MultiBlockType),
+    Delivered(BlockType),
+    Visited(WorldType),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum QuestId {
+    FirstStone,
+    BuildFurnace,
+    MineIron,
+    DeliverIron,
+    VisitCopper,
+    VisitCobalt,
+}
+
+pub enum QuestCondition {
+    Mine(BlockType, u32),
+    Build(MultiBlockType),
+    Deliver(BlockType, u32),
+    Visit(WorldType),
+}
+
+impl QuestCondition {
+    fn matches(&self, event: &QuestEvent) -> bool {
+        match (self, event) {
+            (QuestCondition::Mine(want, _), QuestEvent::Mined(got)) => want == got,
+            (QuestCondition::Build(want), QuestEvent::Built(got)) => want == got,
+            (QuestCondition::Deliver(want, _), QuestEvent::Delivered(got)) => want == got,
+            (QuestCondition::Visit(want), QuestEvent::Visited(got)) => want == got,
+            _ => false,
+        }
+    }
+
+    /// how many matching events are needed to finish
+    fn required(&self) -> u32 {
+        match self {
+            QuestCondition::Mine(_, count) | QuestCondition::Deliver(_, count) => *count,
+            QuestCondition::Build(_) | QuestCondition::Visit(_) => 1,
+        }
+    }
+}
+
+pub struct Quest {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub condition: QuestCondition,
+    /// quests that must be done before this one is shown or tracked
+    pub requires: Vec<QuestId>,
+    pub rewards: Vec<(BlockType, u32)>,
+    pub unlocks: Vec<MultiBlockType>,
+}
+
+/// Resource holding every quest in the order they are shown
+#[derive(Resource)]
+pub struct Quests {
+    quests: Vec<(QuestId, Quest)>,
+    /// recipes that can be built before any quest is done
+    starting_recipes: Vec<MultiBlockType>,
+}
+
+impl FromWorld for Quests {
+    fn from_world(_world: &mut World) -> Self {
+        Quests {
+            starting_recipes: vec![
+                MultiBlockType::Furnace,
+                MultiBlockType::Smelt,
+                MultiBlockType::Score,
+            ],
+            quests: vec![
+                (
+                    QuestId::FirstStone,
+                    Quest {
+                        name: "Breaking Ground",
+                        description: "Mine 10 Stone",
+                        condition: QuestCondition::Mine(BlockType::Stone, 10),
+                        requires: vec![],
+                        rewards: vec![(BlockType::Coal, 4)],
+                        unlocks: vec![],
+                    },
+                ),
+                (
+                    QuestId::BuildFurnace,
+                    Quest {
+                        name: "Getting Warm",
+                        description: "Build a Furnace",
+                        condition: QuestCondition::Build(MultiBlockType::Furnace),
+                        requires: vec![QuestId::FirstStone],
+                        rewards: vec![(BlockType::Coal, 8)],
+                        unlocks: vec![MultiBlockType::IronPickaxe, MultiBlockType::Drill],
+                    },
+                ),
+                (
+                    QuestId::MineIron,
+                    Quest {
+                        name: "Ironing Out",
+                        description: "Mine 10 Iron Ore",
+                        condition: QuestCondition::Mine(BlockType::IronOre, 10),
+                        requires: vec![QuestId::BuildFurnace],
+                        rewards: vec![(BlockType::IronOre, 5)],
+                        unlocks: vec![
+                            MultiBlockType::Piston,
+                            MultiBlockType::Wire,
+                            MultiBlockType::Lever,
+                            MultiBlockType::Button,
+                            MultiBlockType::PressurePlate,
+                            MultiBlockType::ItemDetector,
+                            MultiBlockType::NotGate,
+                            MultiBlockType::AndGate,
+                        ],
+                    },
+                ),
+                (
+                    QuestId::DeliverIron,
+                    Quest {
+                        name: "Special Delivery",
+                        description: "Feed 4 Iron Blocks to a Score block, wanted or not",
+                        condition: QuestCondition::Deliver(BlockType::IronBlock, 4),
+                        requires: vec![QuestId::MineIron],
+                        rewards: vec![(BlockType::IronBlock, 2)],
+                        unlocks: vec![
+                            MultiBlockType::Pipe,
+                            MultiBlockType::Tank,
+                            MultiBlockType::CastingBasin,
+                        ],
+                    },
+                ),
+                (
+                    QuestId::VisitCopper,
+                    Quest {
+                        name: "Seeing Green",
+                        description: "Visit a Copper hex",
+                        condition: QuestCondition::Visit(WorldType::Copper),
+                        requires: vec![QuestId::BuildFurnace],
+                        rewards: vec![],
+                        unlocks: vec![
+                            MultiBlockType::CopperPickaxe,
+                            MultiBlockType::Cable,
+                            MultiBlockType::Generator,
+                            MultiBlockType::Battery,
+                        ],
+                    },
+                ),
+                (
+                    QuestId::VisitCobalt,
+                    Quest {
+                        name: "Feeling Blue",
+                        description: "Visit a Cobalt hex",
+                        condition: QuestCondition::Visit(WorldType::Cobalt),
+                        requires: vec![QuestId::DeliverIron],
+                        rewards: vec![],
+                        unlocks: vec![MultiBlockType::CobaltPickaxe, MultiBlockType::PistonL2],
+                    },
+                ),
+            ],
+        }
+    }
+}
+
+/// Resource with the players progress on each quest
+#[derive(Resource, Default, Serialize, Deserialize, Debug)]
+pub struct QuestLog {
+    progress: HashMap<QuestId, u32>,
+    completed: HashSet<QuestId>,
+}
+
+impl QuestLog {
+    pub fn is_complete(&self, quest: QuestId) -> bool {
+        self.completed.contains(&quest)
+    }
+
+    pub fn progress(&self, quest: QuestId) -> u32 {
+        self.progress.get(&quest).copied().unwrap_or(0)
+    }
+
+    /// a quest is active once everything it requires is done
+    pub fn is_active(&self, id: QuestId, quest: &Quest) -> bool {
+        !self.is_complete(id) && quest.requires.iter().all(|req| self.is_complete(*req))
+    }
+}
+
+/// Resource of every [`MultiBlockType`] the player is allowed to build
+#[derive(Resource, Default)]
+pub struct UnlockedRecipes(HashSet<MultiBlockType>);
+
+impl UnlockedRecipes {
+    pub fn contains(&self, recipe: &MultiBlockType) -> bool {
+        self.0.contains(recipe)
+    }
+
+    #[cfg(test)]
+    pub fn unlock(&mut self, recipe: MultiBlockType) {
+        self.0.insert(recipe);
+    }
+}
+
+fn update_unlocks(quests: Res<Quests>, log: Res<QuestLog>, mut unlocked: ResMut<UnlockedRecipes>) {
+    unlocked.0 = quests.starting_recipes.iter().copied().collect();
+    for (id, quest) in quests.quests.iter() {
+        if log.is_complete(*id) {
+            unlocked.0.extend(quest.unlocks.iter().copied());
+        }
+    }
+}
+
+fn track_quests(
+    trigger: Trigger<QuestEvent>,
+    mut commands: Commands,
+    quests: Res<Quests>,
+    mut log: ResMut<QuestLog>,
+    mut player: Query<&mut Inventory, With<Player>>,
+    blocks: Res<Blocks>,
+    block_data: Res<Assets<Block>>,
+    mut spilled: EventWriter<DropItems>,
+) {
+    for (id, quest) in quests.quests.iter() {
+        if !log.is_active(*id, quest) || !quest.condition.matches(trigger.event()) {
+            continue;
+        }
+        let progress = log.progress.entry(*id).or_insert(0);
+        *progress += 1;
+        if *progress < quest.condition.required() {
+            continue;
+        }
+        log.progress.remove(id);
+        log.completed.insert(*id);
+        info!("Quest complete: {}", quest.name);
+        commands.trigger(PlaySfx::Key(SfxKey::Progress));
+        for mut inventory in &mut player {
+            for (reward, count) in quest.rewards.iter() {
+                let max_stack = blocks.max_stack(&block_data, reward);
+                let overflow = inventory.add_resource(reward.clone(), *count, max_stack);
+                if overflow > 0 {
+                    spilled.send(DropItems {
+                        block_type: reward.clone(),
+                        count: overflow,
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn visit_hex(mut commands: Commands, selected: Res<HexSelect>) {
+    commands.trigger(QuestEvent::Visited(selected.world));
+}
+
+#[derive(Component)]
+struct QuestLogUI;
+
+fn spawn_quest_log(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Quest Log"),
+        QuestLogUI,
+        StateScoped(Screen::VoxelWorld),
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                top: Val::Percent(10.),
+                left: Val::Percent(25.),
+                right: Val::Percent(25.),
+                padding: UiRect::all(Val::Px(10.)),
+                row_gap: Val::Px(6.),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(Color::srgba(0.2, 0.2, 0.2, 0.8)),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+    ));
+}
+
+fn toggle_quest_log(
+    input: Query<&ActionState<PlayerAction>>,
+    mut quest_log: Query<&mut Visibility, With<QuestLogUI>>,
+) {
+    let Ok(input) = input.get_single() else {
+        return;
+    };
+    if !input.just_pressed(&PlayerAction::QuestLog) {
+        return;
+    }
+    for mut visibility in &mut quest_log {
+        *visibility = match *visibility {
+            Visibility::Visible => Visibility::Hidden,
+            _ => Visibility::Visible,
+        };
+    }
+}
+
+fn update_quest_log(
+    mut commands: Commands,
+    quests: Res<Quests>,
+    log: Res<QuestLog>,
+    added: Query<(), Added<QuestLogUI>>,
+    quest_log: Query<Entity, With<QuestLogUI>>,
+) {
+    if added.is_empty() && !log.is_changed() {
+        return;
+    }
+    for entity in &quest_log {
+        commands
+            .entity(entity)
+            .despawn_descendants()
+            .with_children(|list| {
+                list.spawn(TextBundle::from_section(
+                    "Quests",
+                    TextStyle {
+                        font_size: 32.0,
+                        color: crate::ui::palette::HEADER_TEXT,
+                        ..default()
+                    },
+                ));
+                for (id, quest) in quests.quests.iter() {
+                    let status = if log.is_complete(*id) {
+                        "Done".to_string()
+                    } else if log.is_active(*id, quest) {
+                        format!("{}/{}", log.progress(*id), quest.condition.required())
+                    } else {
+                        continue;
+                    };
+                    list.spawn(TextBundle::from_section(
+                        format!("{} - {} ({})", quest.name, quest.description, status),
+                        TextStyle {
+                            font_size: 20.0,
+                            color: crate::ui::palette::LABEL_TEXT,
+                            ..default()
+                        },
+                    ));
+                }
+            });
+    }
+}
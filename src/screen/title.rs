@@ -3,7 +3,10 @@
 use bevy::prelude::*;
 
 use super::Screen;
-use crate::ui::prelude::*;
+use crate::{
+    game::audio::soundtrack::{PlaySoundtrack, SoundtrackContext},
+    ui::prelude::*,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Title), enter_title);
@@ -27,6 +30,7 @@ enum TitleAction {
 }
 
 fn enter_title(mut commands: Commands) {
+    commands.trigger(PlaySoundtrack::Context(SoundtrackContext::Title));
     commands
         .ui_root()
         .insert(StateScoped(Screen::Title))
@@ -0,0 +1,359 @@
+//! Inventory pictures of blocks, drawn on the CPU as small isometric views.
+//!
+//! Plain blocks are drawn as a cube with their texture, compressed voxels as the chunk they
+//! were made from and machines as the structure their multi block recipe is built from.
+
+use bevy::{
+    color::{LinearRgba, Srgba},
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+    utils::HashMap,
+};
+
+use crate::{game::main_character::Player, screen::inventory::Inventory};
+
+use super::{
+    voxel_block_generation::VoxelDataMap,
+    voxels::{Block, BlockType, Blocks},
+    world::{multi_block::MultiBlocks, VoxelChunk, CHUNK_SIZE},
+};
+
+/// Width and height in pixels of every icon
+pub const ICON_SIZE: u32 = 64;
+
+/// Pixels left clear around the drawing
+const ICON_PADDING: f32 = 2.;
+
+/// Resource caching the icon drawn for each block, filled in by [`generate_block_icons`]
+#[derive(Resource, Default)]
+pub struct BlockIcons {
+    icons: HashMap<BlockType, Handle<Image>>,
+}
+
+impl BlockIcons {
+    pub fn get(&self, block: &BlockType) -> Option<Handle<Image>> {
+        self.icons.get(block).cloned()
+    }
+}
+
+/// Draws an icon for every block in the player's inventory that does not have one yet.
+/// Blocks whose textures or chunks are still loading are tried again on a later frame.
+pub fn generate_block_icons(
+    mut icons: ResMut<BlockIcons>,
+    inventory_query: Query<&Inventory, With<Player>>,
+    voxels: Res<Blocks>,
+    voxel_data: Res<Assets<Block>>,
+    materials: Res<Assets<StandardMaterial>>,
+    voxel_map: Res<VoxelDataMap>,
+    chunks: Res<Assets<VoxelChunk>>,
+    recipes: Res<MultiBlocks>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for inventory in &inventory_query {
+        for slot in inventory.slots.iter() {
+            let Some(block) = &slot.resource_type else {
+                continue;
+            };
+            if icons.icons.contains_key(block) {
+                continue;
+            }
+            let icon = match block {
+                BlockType::Voxel(id) => voxel_map
+                    .chunk(*id)
+                    .and_then(|handle| chunks.get(handle.id()))
+                    .map(chunk_icon),
+                _ => match recipes.preview(block) {
+                    Some((size, structure)) => Some(structure_icon(size, &structure)),
+                    None => block_icon(block, &voxels, &voxel_data, &materials, &images),
+                },
+            };
+            if let Some(icon) = icon {
+                icons.icons.insert(block.clone(), images.add(icon));
+            }
+        }
+    }
+}
+
+/// A single block as a textured cube, `None` until its block and texture have loaded
+fn block_icon(
+    block: &BlockType,
+    voxels: &Blocks,
+    voxel_data: &Assets<Block>,
+    materials: &Assets<StandardMaterial>,
+    images: &Assets<Image>,
+) -> Option<Image> {
+    let block = voxel_data.get(voxels.get(block.clone()).id())?;
+    let material = materials.get(block.material().id())?;
+    let texture = match &material.base_color_texture {
+        Some(texture) => Some(images.get(texture.id())?),
+        None => None,
+    };
+    let color = block.color();
+    Some(rasterize_icon(IVec3::ONE, |_| Some(color), texture))
+}
+
+/// A compressed voxel as the chunk it was made from
+fn chunk_icon(chunk: &VoxelChunk) -> Image {
+    rasterize_icon(
+        IVec3::splat(CHUNK_SIZE as i32),
+        |pos| {
+            let block = chunk.get(pos);
+            (block != BlockType::Air).then(|| block.color())
+        },
+        None,
+    )
+}
+
+/// A machine as the blocks it is built from, indexed like multi block rules
+fn structure_icon(size: IVec3, structure: &[BlockType]) -> Image {
+    rasterize_icon(
+        size,
+        |pos| {
+            let block = &structure[(pos.x + pos.z * size.x + pos.y * size.x * size.z) as usize];
+            (block != &BlockType::Air).then(|| block.color())
+        },
+        None,
+    )
+}
+
+/// How much light each visible face of a voxel gets, so the cube reads as solid
+const TOP_SHADE: f32 = 1.;
+const RIGHT_SHADE: f32 = 0.8;
+const LEFT_SHADE: f32 = 0.6;
+
+/// Where a point lands on the icon before scaling, looking down at the +x, +y and +z faces
+fn project(point: Vec3) -> Vec2 {
+    let cos30 = 3f32.sqrt() / 2.;
+    Vec2::new(
+        (point.x - point.z) * cos30,
+        (point.x + point.z) / 2. - point.y,
+    )
+}
+
+/// Draws the voxels in `0..size` as an isometric [`ICON_SIZE`] square image.
+///
+/// `voxel` gives the colour of each voxel or `None` where it is empty. When `texture` is set
+/// every face is painted with it, tinted by the voxel colour.
+pub fn rasterize_icon(
+    size: IVec3,
+    voxel: impl Fn(IVec3) -> Option<Color>,
+    texture: Option<&Image>,
+) -> Image {
+    let width = ICON_SIZE as usize;
+    let mut pixels = vec![[0u8; 4]; width * width];
+    // how close to the viewer the drawn point of each pixel is
+    let mut depth = vec![f32::NEG_INFINITY; width * width];
+
+    // fit the whole box into the icon
+    let corners = (0..8).map(|i| {
+        project(Vec3::new(
+            (i & 1) as f32 * size.x as f32,
+            ((i >> 1) & 1) as f32 * size.y as f32,
+            ((i >> 2) & 1) as f32 * size.z as f32,
+        ))
+    });
+    let (min, max) = corners.fold(
+        (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+        |(min, max), corner| (min.min(corner), max.max(corner)),
+    );
+    let extent = max - min;
+    let scale = (ICON_SIZE as f32 - 2. * ICON_PADDING) / extent.max_element();
+    let offset = (Vec2::splat(ICON_SIZE as f32) - extent * scale) / 2. - min * scale;
+
+    let inside = |pos: IVec3| pos.cmpge(IVec3::ZERO).all() && pos.cmplt(size).all();
+    let solid = |pos: IVec3| inside(pos) && voxel(pos).is_some();
+    // each visible face as its normal, top left corner, the two edges from it and its shade
+    let faces = [
+        (IVec3::Y, Vec3::Y, Vec3::X, Vec3::Z, TOP_SHADE),
+        (
+            IVec3::X,
+            Vec3::new(1., 1., 0.),
+            Vec3::Z,
+            Vec3::NEG_Y,
+            RIGHT_SHADE,
+        ),
+        (
+            IVec3::Z,
+            Vec3::new(0., 1., 1.),
+            Vec3::X,
+            Vec3::NEG_Y,
+            LEFT_SHADE,
+        ),
+    ];
+
+    for x in 0..size.x {
+        for y in 0..size.y {
+            for z in 0..size.z {
+                let pos = IVec3::new(x, y, z);
+                let Some(color) = voxel(pos) else {
+                    continue;
+                };
+                let color = color.to_linear();
+                for (normal, corner, across, down, shade) in faces {
+                    if solid(pos + normal) {
+                        continue;
+                    }
+                    let corner = pos.as_vec3() + corner;
+                    let origin = project(corner) * scale + offset;
+                    let a = project(across) * scale;
+                    let b = project(down) * scale;
+                    let inverse = Mat2::from_cols(a, b).inverse();
+                    let points = [origin, origin + a, origin + b, origin + a + b];
+                    let low = points.iter().fold(Vec2::INFINITY, |low, p| low.min(*p));
+                    let high = points
+                        .iter()
+                        .fold(Vec2::NEG_INFINITY, |high, p| high.max(*p));
+                    let low = low.floor().max(Vec2::ZERO).as_uvec2();
+                    let high = high.ceil().min(Vec2::splat(ICON_SIZE as f32)).as_uvec2();
+                    for py in low.y..high.y {
+                        for px in low.x..high.x {
+                            let centre = Vec2::new(px as f32, py as f32) + 0.5;
+                            let uv = inverse * (centre - origin);
+                            if uv.cmplt(Vec2::ZERO).any() || uv.cmpge(Vec2::ONE).any() {
+                                continue;
+                            }
+                            let point = corner + across * uv.x + down * uv.y;
+                            let index = py as usize * width + px as usize;
+                            let closeness = point.x + point.y + point.z;
+                            if closeness <= depth[index] {
+                                continue;
+                            }
+                            let base = match texture {
+                                Some(texture) => match sample(texture, uv) {
+                                    Some(sampled) => sampled,
+                                    None => continue,
+                                },
+                                None => LinearRgba::WHITE,
+                            };
+                            depth[index] = closeness;
+                            pixels[index] = to_srgba_bytes(LinearRgba::new(
+                                base.red * color.red * shade,
+                                base.green * color.green * shade,
+                                base.blue * color.blue * shade,
+                                base.alpha * color.alpha,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width: ICON_SIZE,
+            height: ICON_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        pixels.concat(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+/// The texel of `texture` at `uv`, `None` where it is see through. Formats other than 8 bit
+/// rgba are drawn as plain white.
+fn sample(texture: &Image, uv: Vec2) -> Option<LinearRgba> {
+    let format = texture.texture_descriptor.format;
+    if !matches!(
+        format,
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm
+    ) {
+        return Some(LinearRgba::WHITE);
+    }
+    let (width, height) = (texture.width(), texture.height());
+    let x = ((uv.x * width as f32) as u32).min(width - 1);
+    let y = ((uv.y * height as f32) as u32).min(height - 1);
+    let index = (y * width + x) as usize * 4;
+    let [r, g, b, a]: [u8; 4] = texture.data.get(index..index + 4)?.try_into().ok()?;
+    if a == 0 {
+        return None;
+    }
+    Some(if format == TextureFormat::Rgba8UnormSrgb {
+        Srgba::rgba_u8(r, g, b, a).into()
+    } else {
+        LinearRgba::new(
+            r as f32 / 255.,
+            g as f32 / 255.,
+            b as f32 / 255.,
+            a as f32 / 255.,
+        )
+    })
+}
+
+fn to_srgba_bytes(color: LinearRgba) -> [u8; 4] {
+    let color = Srgba::from(color);
+    [color.red, color.green, color.blue, color.alpha]
+        .map(|channel| (channel.clamp(0., 1.) * 255.).round() as u8)
+}
+
+#[cfg(test)]
+fn pixel(image: &Image, x: u32, y: u32) -> [u8; 4] {
+    let index = (y * image.width() + x) as usize * 4;
+    image.data[index..index + 4].try_into().unwrap()
+}
+
+#[test]
+fn a_cube_shows_a_lit_top_and_shaded_sides() {
+    let icon = rasterize_icon(IVec3::ONE, |_| Some(Color::WHITE), None);
+    assert_eq!(icon.width(), ICON_SIZE);
+    // the corners are outside the hexagon the cube makes
+    assert_eq!(pixel(&icon, 0, 0)[3], 0);
+    assert_eq!(pixel(&icon, ICON_SIZE - 1, ICON_SIZE - 1)[3], 0);
+    let top = pixel(&icon, ICON_SIZE / 2, ICON_SIZE / 4);
+    let right = pixel(&icon, ICON_SIZE * 3 / 4, ICON_SIZE * 5 / 8);
+    let left = pixel(&icon, ICON_SIZE / 4, ICON_SIZE * 5 / 8);
+    assert_eq!(top, [255; 4]);
+    assert!(top[0] > right[0] && right[0] > left[0]);
+    assert_eq!(left[3], 255);
+}
+
+#[test]
+fn nearer_voxels_cover_the_ones_behind() {
+    let red = Color::srgb(1., 0., 0.);
+    let blue = Color::srgb(0., 0., 1.);
+    // a red voxel in front of a blue one, seen from the +x +y +z corner
+    let icon = rasterize_icon(
+        IVec3::ONE * 2,
+        |pos| match pos {
+            IVec3::ZERO => Some(blue),
+            IVec3::ONE => Some(red),
+            _ => None,
+        },
+        None,
+    );
+    let centre = pixel(&icon, ICON_SIZE / 2, ICON_SIZE / 2);
+    assert!(centre[0] > 0 && centre[2] == 0);
+}
+
+#[test]
+fn an_empty_chunk_draws_nothing() {
+    let icon = chunk_icon(&VoxelChunk::new());
+    assert!(icon.data.iter().all(|byte| *byte == 0));
+}
+
+#[test]
+fn textures_are_sampled_across_each_face() {
+    // left half black and right half white
+    let texture = Image::new(
+        Extent3d {
+            width: 2,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        vec![0, 0, 0, 255, 255, 255, 255, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    let icon = rasterize_icon(IVec3::ONE, |_| Some(Color::WHITE), Some(&texture));
+    // the top face runs from its back corner along +x towards the right
+    let back = pixel(&icon, ICON_SIZE / 2 - 4, ICON_SIZE / 4);
+    let right = pixel(&icon, ICON_SIZE / 2 + 8, ICON_SIZE / 4);
+    assert_eq!(back[0], 0);
+    assert_eq!(right[0], 255);
+}
@@ -13,14 +13,39 @@ pub struct ItemPlugin;
 
 impl Plugin for ItemPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(FixedUpdate, kill_layer)
-            .add_systems(Update, (pickup_item, update_icon));
+        app.add_event::<DropSlot>()
+            .add_event::<DropItems>()
+            .add_systems(FixedUpdate, kill_layer)
+            .add_systems(
+                Update,
+                (pickup_item, update_icon, drop_slots).run_if(in_state(Screen::VoxelWorld)),
+            )
+            .add_systems(Update, drop_items);
     }
 }
 
 #[derive(Component)]
 pub struct Item;
 
+/// Most items thrown out of a slot at once, or in a frame for what did not fit in the inventory,
+/// so a big stack does not flood the physics
+pub const MAX_DROPPED_ITEMS: u32 = 16;
+
+/// Send this with what did not fit in the inventory, it is dropped at the player instead.
+/// Items given outside a voxel world wait there until the player enters one
+#[derive(Event, Debug, Clone)]
+pub struct DropItems {
+    pub block_type: BlockType,
+    pub count: u32,
+}
+
+/// Send this to throw up to `count` items from an inventory slot out in front of the player
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DropSlot {
+    pub index: usize,
+    pub count: u32,
+}
+
 pub fn spawn_item(
     block_type: BlockType,
     blocks: &Assets<Block>,
@@ -52,6 +77,8 @@ pub fn pickup_item(
     mut vox_player: Query<(&Parent, &GlobalTransform), With<VoxelPlayer>>,
     mut player_inventory: Query<&mut Inventory, With<Player>>,
     mut voxels: Query<&BlockType, With<Item>>,
+    blocks: Res<Assets<Block>>,
+    block_types: Res<Blocks>,
 ) {
     if !input.just_pressed(MouseButton::Left) {
         return;
@@ -65,8 +92,15 @@ pub fn pickup_item(
             QueryFilter::new().exclude_rigid_body(ignore.get()),
         ) {
             if let Ok(block) = voxels.get_mut(hit) {
-                commands.entity(hit).despawn();
-                player_inventory.single_mut().add_resource(block.clone(), 1);
+                let max_stack = block_types.max_stack(&blocks, block);
+                // items that do not fit stay where they are
+                if player_inventory
+                    .single_mut()
+                    .add_resource(block.clone(), 1, max_stack)
+                    == 0
+                {
+                    commands.entity(hit).despawn();
+                }
             }
         }
     }
@@ -95,3 +129,80 @@ pub fn update_icon(
         *material = block.material();
     }
 }
+
+/// Spawns `count` items stacked up in front of the player
+fn throw_items(
+    commands: &mut Commands,
+    player: &GlobalTransform,
+    block_type: &BlockType,
+    count: u32,
+    blocks: &Assets<Block>,
+    voxels: &Blocks,
+) {
+    let front = player.translation() + player.forward().as_vec3() * 1.5;
+    for i in 0..count {
+        // stacked up so they do not start inside each other
+        let offset = front + Vec3::Y * 0.5 * i as f32;
+        spawn_item(block_type.clone(), blocks, voxels, offset, commands);
+    }
+}
+
+fn drop_slots(
+    mut commands: Commands,
+    mut drops: EventReader<DropSlot>,
+    mut player_inventory: Query<&mut Inventory, With<Player>>,
+    vox_player: Query<&GlobalTransform, With<VoxelPlayer>>,
+    blocks: Res<Assets<Block>>,
+    voxels: Res<Blocks>,
+) {
+    let (Ok(mut inventory), Ok(player)) =
+        (player_inventory.get_single_mut(), vox_player.get_single())
+    else {
+        drops.clear();
+        return;
+    };
+    for drop in drops.read() {
+        let Some(slot) = inventory.slots.get_mut(drop.index) else {
+            continue;
+        };
+        let Some(block_type) = slot.resource_type.clone() else {
+            continue;
+        };
+        let count = drop.count.min(slot.quantity);
+        slot.quantity -= count;
+        if slot.quantity == 0 {
+            slot.resource_type = None;
+        }
+        throw_items(&mut commands, player, &block_type, count, &blocks, &voxels);
+    }
+}
+
+fn drop_items(
+    mut commands: Commands,
+    mut drops: EventReader<DropItems>,
+    mut waiting: Local<Vec<DropItems>>,
+    vox_player: Query<&GlobalTransform, With<VoxelPlayer>>,
+    blocks: Res<Assets<Block>>,
+    voxels: Res<Blocks>,
+) {
+    waiting.extend(drops.read().cloned());
+    let Ok(player) = vox_player.get_single() else {
+        return;
+    };
+    // the rest stays queued for the following frames
+    let mut left = MAX_DROPPED_ITEMS;
+    waiting.retain_mut(|drop| {
+        let count = drop.count.min(left);
+        throw_items(
+            &mut commands,
+            player,
+            &drop.block_type,
+            count,
+            &blocks,
+            &voxels,
+        );
+        left -= count;
+        drop.count -= count;
+        drop.count > 0
+    });
+}
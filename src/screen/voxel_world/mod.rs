@@ -1,7 +1,8 @@
 //! The screen state for the voxel world game loop.
 
-mod item;
-mod player_controller;
+pub mod block_icons;
+pub mod item;
+pub mod player_controller;
 pub mod ui;
 mod voxel_block_generation;
 pub mod voxel_util;
@@ -12,17 +13,21 @@ use super::{
     Screen,
 };
 use crate::game::{
-    assets::SoundtrackKey,
-    audio::soundtrack::PlaySoundtrack,
+    action_just_pressed,
+    audio::soundtrack::{PlaySoundtrack, SoundtrackContext},
     save::{inventory_save, save_chunk_data},
-    PlayerAction,
+    HexSelect, PlayerAction,
 };
-use bevy::{input::common_conditions::input_just_pressed, prelude::*};
-use leafwing_input_manager::prelude::ActionState;
+use crate::ui::interaction::apply_interaction_palette;
+use bevy::prelude::*;
+use block_icons::{generate_block_icons, BlockIcons};
+use leafwing_input_manager::{plugin::InputManagerSystem, prelude::ActionState};
 use player_controller::spawn_player;
 use ui::{
-    cleanup_inventory_ui, handle_slot_selection, setup_inventory_ui, toggle_full_inventory,
-    update_inventory_ui,
+    back_out_of_inventory, capture_gameplay_input, cleanup_inventory_ui, handle_slot_selection,
+    move_inventory_slots, refresh_inventory_ui, release_dragged_slot, run_slot_actions,
+    setup_inventory_ui, show_drag_ghost, show_held_slot, sort_inventory, spawn_hud_column,
+    toggle_full_inventory, HeldSlot,
 };
 
 pub mod voxels;
@@ -36,11 +41,35 @@ pub(super) fn plugin(app: &mut App) {
             enter_playing,
             spawn_player,
             setup_inventory_ui.after(spawn_player),
+            spawn_hud_column,
         ),
     );
+    app.init_resource::<HeldSlot>();
+    app.init_resource::<BlockIcons>();
     app.add_systems(
         Update,
-        (update_inventory_ui, handle_slot_selection).run_if(in_state(Screen::VoxelWorld)),
+        (
+            (generate_block_icons, refresh_inventory_ui).chain(),
+            handle_slot_selection,
+        )
+            .run_if(in_state(Screen::VoxelWorld)),
+    );
+    app.add_systems(
+        Update,
+        (
+            (move_inventory_slots, release_dragged_slot, show_drag_ghost).chain(),
+            run_slot_actions,
+            sort_inventory,
+            back_out_of_inventory,
+            show_held_slot.after(apply_interaction_palette),
+        )
+            .run_if(in_state(Screen::VoxelWorld)),
+    );
+    app.add_systems(
+        PreUpdate,
+        capture_gameplay_input
+            .after(InputManagerSystem::Update)
+            .run_if(in_state(Screen::VoxelWorld)),
     );
     app.add_systems(
         OnExit(Screen::VoxelWorld),
@@ -58,8 +87,9 @@ pub(super) fn plugin(app: &mut App) {
     );
     app.add_systems(
         Update,
-        toggle_full_inventory
-            .run_if(in_state(Screen::VoxelWorld).and_then(input_just_pressed(KeyCode::KeyT))),
+        toggle_full_inventory.run_if(
+            in_state(Screen::VoxelWorld).and_then(action_just_pressed(PlayerAction::Inventory)),
+        ),
     );
     app.add_plugins(player_controller::VoxelCamera);
     app.register_type::<Inventory>();
@@ -82,8 +112,10 @@ pub(super) fn plugin(app: &mut App) {
     world::voxel_world(app);
 }
 
-fn enter_playing(mut commands: Commands) {
-    commands.trigger(PlaySoundtrack::Key(SoundtrackKey::Gameplay));
+fn enter_playing(mut commands: Commands, selected: Res<HexSelect>) {
+    commands.trigger(PlaySoundtrack::Context(SoundtrackContext::VoxelWorld(
+        selected.world,
+    )));
 }
 
 fn exit_playing(mut commands: Commands) {
@@ -101,7 +101,12 @@ fn player_look(
             if let Some(data) = input.axis_pair(&PlayerAction::Look) {
                 let (mut yaw, mut pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
                 let window_scale = window.height().min(window.width());
-                pitch -= (settings.mouse_sensitivity * data.y() * window_scale).to_radians();
+                let look_y = if settings.invert_y {
+                    -data.y()
+                } else {
+                    data.y()
+                };
+                pitch -= (settings.mouse_sensitivity * look_y * window_scale).to_radians();
                 yaw -= (settings.mouse_sensitivity * data.x() * window_scale).to_radians();
                 transform.rotation =
                     Quat::from_axis_angle(Vec3::Y, yaw) * Quat::from_axis_angle(Vec3::X, pitch);
@@ -131,12 +136,14 @@ fn cursor_grab(mut primary_window: Query<&mut Window, With<PrimaryWindow>>) {
 }
 
 fn cursor_toggle(
-    keys: Res<ButtonInput<KeyCode>>,
-    key_bindings: Res<VoxelSettings>,
+    input: Query<&ActionState<PlayerAction>>,
     mut primary_window: Query<&mut Window, With<PrimaryWindow>>,
 ) {
+    let Ok(input) = input.get_single() else {
+        return;
+    };
     if let Ok(mut window) = primary_window.get_single_mut() {
-        if keys.just_pressed(key_bindings.toggle_grab_cursor) {
+        if input.just_pressed(&PlayerAction::ToggleCursor) {
             match window.cursor.grab_mode {
                 CursorGrabMode::None => {
                     window.cursor.grab_mode = CursorGrabMode::Confined;
@@ -153,17 +160,31 @@ fn cursor_toggle(
     }
 }
 
-#[derive(Resource)]
+/// Resource of the player's control settings, saved to the store when the video menu closes
+#[derive(Resource, Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct VoxelSettings {
-    pub toggle_grab_cursor: KeyCode,
     pub mouse_sensitivity: f32,
+    pub invert_y: bool,
+}
+
+impl VoxelSettings {
+    pub const DEFAULT_SENSITIVITY: f32 = 0.00012;
+
+    /// Moves the sensitivity by `amount` times the default, between a tenth and three times it
+    pub fn adjust_sensitivity(&mut self, amount: f32) {
+        let scale = ((self.mouse_sensitivity / Self::DEFAULT_SENSITIVITY + amount).clamp(0.1, 3.)
+            * 10.)
+            .round()
+            / 10.;
+        self.mouse_sensitivity = scale * Self::DEFAULT_SENSITIVITY;
+    }
 }
 
 impl Default for VoxelSettings {
     fn default() -> Self {
         Self {
-            toggle_grab_cursor: KeyCode::Backquote,
-            mouse_sensitivity: 0.00012,
+            mouse_sensitivity: Self::DEFAULT_SENSITIVITY,
+            invert_y: false,
         }
     }
 }
@@ -255,6 +276,8 @@ pub fn spawn_player(mut commands: Commands, hex_select: Res<HexSelect>) {
                     ..Default::default()
                 },
                 VoxelPlayer,
+                // block and machine sounds are heard from here
+                SpatialListener::new(0.3),
             ))
             .with_children(|p| {
                 p.spawn(SpotLightBundle {
@@ -1,42 +1,120 @@
 use crate::{
     game::{main_character::Player, PlayerAction},
-    screen::inventory::Inventory,
-    ui::widgets::{Containers, UiRoot, Widgets},
+    screen::{inventory::Inventory, Screen},
+    ui::{
+        navigation::{UiAction, UiFocus},
+        prelude::{InteractionPalette, InteractionQuery},
+        widgets::{slot_image, slot_name, Containers, UiRoot, Widgets},
+    },
 };
-use bevy::{input::mouse::MouseWheel, prelude::*};
+use bevy::{prelude::*, ui::Val::*, window::PrimaryWindow};
 use leafwing_input_manager::prelude::ActionState;
 
-use super::voxels::{Block, Blocks}; // Adjust this path as needed
+use super::{
+    block_icons::BlockIcons,
+    item::{DropSlot, MAX_DROPPED_ITEMS},
+    voxels::{Block, BlockType, Blocks},
+}; // Adjust this path as needed
 
 #[derive(Component)]
 pub struct FullInventoryUI;
 
+#[derive(Component)]
+pub struct HotbarUI;
+
+/// A slot of the full inventory, holding its index in [`Inventory::slots`]
+#[derive(Component)]
+pub struct InventorySlotButton(pub usize);
+
+/// A cell of the hotbar, showing this column of the selected row
+#[derive(Component)]
+pub struct HotbarSlot(pub usize);
+
+/// The picture of a slot, showing the slot at this index of [`Inventory::slots`]
+#[derive(Component)]
+pub struct SlotContents(pub usize);
+
+/// Text of a slot with the name of what it holds
+#[derive(Component)]
+pub struct SlotName;
+
+/// Text of a slot with how many it holds
+#[derive(Component)]
+pub struct SlotQuantity;
+
+#[derive(Component)]
+pub struct SortInventoryButton;
+
+/// The picture of a dragged slot, following the cursor
+#[derive(Component)]
+pub struct DragGhost;
+
+/// Resource for the slot picked up by pressing it, put down on the next slot pressed
+#[derive(Resource, Default)]
+pub struct HeldSlot {
+    pub slot: Option<usize>,
+    /// picked up with the mouse, so letting go of the button puts it down
+    pub dragging: bool,
+}
+
+/// Size in pixels of the picture following the cursor while dragging
+const DRAG_GHOST_SIZE: f32 = 48.;
+
+/// The column in the top right corner that panels over the voxel world stack down
+#[derive(Component)]
+pub struct HudColumn;
+
+pub fn spawn_hud_column(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Hud Column"),
+        HudColumn,
+        StateScoped(Screen::VoxelWorld),
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Px(10.),
+                right: Px(10.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
+                row_gap: Px(10.),
+                ..default()
+            },
+            ..default()
+        },
+    ));
+}
+
 pub fn setup_inventory_ui(
     mut commands: Commands,
     player_query: Query<(&Inventory, &Player)>,
-    voxels: Res<Blocks>,
-    voxel_data: Res<Assets<Block>>,
-    materials: Res<Assets<StandardMaterial>>,
+    icons: Res<BlockIcons>,
 ) {
     if let Ok(player_inventory) = player_query.get_single() {
         commands
             .ui_root() // Assuming you have this method from the Containers trait
             .with_children(|parent| {
-                parent.hotbar(player_inventory.0, &voxels, &voxel_data, &materials);
-                parent.full_inventory(player_inventory.0, &voxels, &voxel_data, &materials);
+                parent.hotbar(player_inventory.0, &icons);
+                parent.full_inventory(player_inventory.0, &icons);
             });
     }
 }
 
-pub fn cleanup_inventory_ui(mut commands: Commands, ui_query: Query<Entity, With<UiRoot>>) {
+pub fn cleanup_inventory_ui(
+    mut commands: Commands,
+    ui_query: Query<Entity, With<UiRoot>>,
+    mut held: ResMut<HeldSlot>,
+) {
     for entity in ui_query.iter() {
         commands.entity(entity).despawn_recursive();
     }
+    *held = HeldSlot::default();
 }
 
 pub fn toggle_full_inventory(
     mut inventory_ui_query: Query<(Entity, &mut Visibility), With<FullInventoryUI>>,
+    mut held: ResMut<HeldSlot>,
 ) {
+    *held = HeldSlot::default();
     if let Ok((_, mut visibility)) = inventory_ui_query.get_single_mut() {
         // Toggle visibility of existing inventory UI
         *visibility = match *visibility {
@@ -46,51 +124,73 @@ pub fn toggle_full_inventory(
     }
 }
 
-pub fn update_inventory_ui(
-    mut commands: Commands,
-    player_query: Query<&Inventory, (With<Player>, Changed<Inventory>)>,
-    ui_root_query: Query<Entity, With<UiRoot>>,
-    voxels: Res<Blocks>,
-    voxel_data: Res<Assets<Block>>,
-    materials: Res<Assets<StandardMaterial>>,
+/// Updates the slots in place when the inventory changes or new icons have been drawn
+pub fn refresh_inventory_ui(
+    player_query: Query<Ref<Inventory>, With<Player>>,
+    mut hotbar_query: Query<(&HotbarSlot, &mut BackgroundColor, &Children), Without<SlotContents>>,
+    mut slot_query: Query<(
+        &mut SlotContents,
+        &mut UiImage,
+        &mut BackgroundColor,
+        &Children,
+    )>,
+    mut text_query: Query<(&mut Text, Has<SlotName>), Or<(With<SlotName>, With<SlotQuantity>)>>,
+    icons: Res<BlockIcons>,
 ) {
-    if let Ok(inventory) = player_query.get_single() {
-        println!("Update Inventory");
-        if let Ok(ui_root) = ui_root_query.get_single() {
-            // Remove the old inventory UI
-            commands.entity(ui_root).despawn_descendants();
-
-            // Spawn the new inventory UI
-            commands.entity(ui_root).with_children(|parent| {
-                parent.hotbar(inventory, &voxels, &voxel_data, &materials);
-                parent.full_inventory(inventory, &voxels, &voxel_data, &materials);
-            });
+    let Ok(inventory) = player_query.get_single() else {
+        return;
+    };
+    if !inventory.is_changed() && !icons.is_changed() {
+        return;
+    }
+    // the hotbar shows the selected row, so its slots change with it
+    for (column, mut background, children) in &mut hotbar_query {
+        let index = inventory.selected_row * 10 + column.0;
+        background.0 = if index == inventory.selected_slot {
+            Color::srgb(1.0, 0.0, 0.0)
+        } else {
+            Color::NONE
+        };
+        for child in children {
+            if let Ok((mut contents, ..)) = slot_query.get_mut(*child) {
+                contents.0 = index;
+            }
+        }
+    }
+    for (contents, mut image, mut background, children) in &mut slot_query {
+        let Some(slot) = inventory.slots.get(contents.0) else {
+            continue;
+        };
+        let (texture, color) = slot_image(slot, &icons);
+        if image.texture != texture {
+            image.texture = texture;
+        }
+        background.0 = color;
+        for child in children {
+            let Ok((mut text, is_name)) = text_query.get_mut(*child) else {
+                continue;
+            };
+            let value = if is_name {
+                slot_name(slot)
+            } else {
+                slot.quantity.to_string()
+            };
+            if text.sections[0].value != value {
+                text.sections[0].value = value;
+            }
         }
     }
 }
 
 pub fn handle_slot_selection(
     mut inventory_query: Query<&mut Inventory, With<Player>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut mouse_wheel: EventReader<MouseWheel>,
     input: Query<&ActionState<PlayerAction>>,
 ) {
-    for (i, key) in [
-        KeyCode::Digit1,
-        KeyCode::Digit2,
-        KeyCode::Digit3,
-        KeyCode::Digit4,
-        KeyCode::Digit5,
-        KeyCode::Digit6,
-        KeyCode::Digit7,
-        KeyCode::Digit8,
-        KeyCode::Digit9,
-        KeyCode::Digit0,
-    ]
-    .iter()
-    .enumerate()
-    {
-        if keyboard_input.just_pressed(*key) {
+    let Ok(input) = input.get_single() else {
+        return;
+    };
+    for (i, action) in PlayerAction::SLOTS.iter().enumerate() {
+        if input.just_pressed(action) {
             if let Ok(mut inventory) = inventory_query.get_single_mut() {
                 inventory.select_slot(i);
                 break;
@@ -98,10 +198,8 @@ pub fn handle_slot_selection(
         }
     }
     let mut delta = 0;
-    if let Ok(input) = input.get_single() {
-        delta += input.just_pressed(&PlayerAction::ItemInc) as isize;
-        delta -= input.just_pressed(&PlayerAction::ItemDec) as isize;
-    }
+    delta += input.just_pressed(&PlayerAction::ItemInc) as isize;
+    delta -= input.just_pressed(&PlayerAction::ItemDec) as isize;
     if delta > 1 {
         if let Ok(mut inventory) = inventory_query.get_single_mut() {
             let new = (inventory.selected_slot as isize + delta) % 10;
@@ -109,3 +207,272 @@ pub fn handle_slot_selection(
         }
     }
 }
+
+/// Pressing a slot picks it up and pressing another puts it down there, so stacks can be moved
+/// with a gamepad as well as the mouse
+pub fn move_inventory_slots(
+    button_query: InteractionQuery<&InventorySlotButton>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut held: ResMut<HeldSlot>,
+    mut inventory_query: Query<&mut Inventory, With<Player>>,
+    voxels: Res<Blocks>,
+    voxel_data: Res<Assets<Block>>,
+) {
+    let max_stack = |block: &BlockType| voxels.max_stack(&voxel_data, block);
+    for (interaction, slot) in &button_query {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        let Ok(mut inventory) = inventory_query.get_single_mut() else {
+            return;
+        };
+        // shift clicking moves the stack straight across without picking it up
+        if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            *held = HeldSlot::default();
+            inventory.quick_move(slot.0, max_stack);
+            continue;
+        }
+        match held.slot.take() {
+            Some(from) if from != slot.0 => inventory.move_slot(from, slot.0, max_stack),
+            // pressing the held slot again puts it back
+            Some(_) => {}
+            None if inventory.slots[slot.0].resource_type.is_some() => {
+                held.slot = Some(slot.0);
+                held.dragging = mouse.just_pressed(MouseButton::Left);
+            }
+            None => {}
+        }
+    }
+}
+
+/// Letting go of a dragged slot puts it down on the slot under the cursor, or throws it into
+/// the world when the cursor is off the inventory
+pub fn release_dragged_slot(
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut held: ResMut<HeldSlot>,
+    slot_query: Query<(&Interaction, &InventorySlotButton)>,
+    panel_query: Query<&Interaction, Or<(With<FullInventoryUI>, With<HotbarUI>)>>,
+    mut inventory_query: Query<&mut Inventory, With<Player>>,
+    mut drops: EventWriter<DropSlot>,
+    voxels: Res<Blocks>,
+    voxel_data: Res<Assets<Block>>,
+) {
+    if !held.dragging || !mouse.just_released(MouseButton::Left) {
+        return;
+    }
+    held.dragging = false;
+    let Some(from) = held.slot else {
+        return;
+    };
+    let target = slot_query
+        .iter()
+        .find(|(interaction, _)| **interaction != Interaction::None)
+        .map(|(_, slot)| slot.0);
+    match target {
+        // let go where it was picked up, so it stays held until another slot is pressed
+        Some(to) if to == from => {}
+        Some(to) => {
+            held.slot = None;
+            if let Ok(mut inventory) = inventory_query.get_single_mut() {
+                inventory.move_slot(from, to, |block: &BlockType| {
+                    voxels.max_stack(&voxel_data, block)
+                });
+            }
+        }
+        None if panel_query
+            .iter()
+            .any(|interaction| *interaction != Interaction::None) =>
+        {
+            held.slot = None
+        }
+        None => {
+            held.slot = None;
+            drops.send(DropSlot {
+                index: from,
+                count: MAX_DROPPED_ITEMS,
+            });
+        }
+    }
+}
+
+/// Keeps a picture of the dragged slot under the cursor
+pub fn show_drag_ghost(
+    mut commands: Commands,
+    held: Res<HeldSlot>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    slot_query: Query<(&SlotContents, &UiImage, &BackgroundColor), Without<DragGhost>>,
+    mut ghost_query: Query<(Entity, &mut Style), With<DragGhost>>,
+) {
+    let cursor = window_query
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+    let (Some(from), true, Some(cursor)) = (held.slot, held.dragging, cursor) else {
+        for (entity, _) in &ghost_query {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+    let left = Px(cursor.x - DRAG_GHOST_SIZE / 2.);
+    let top = Px(cursor.y - DRAG_GHOST_SIZE / 2.);
+    if let Ok((_, mut style)) = ghost_query.get_single_mut() {
+        style.left = left;
+        style.top = top;
+        return;
+    }
+    let Some((_, image, color)) = slot_query.iter().find(|(contents, ..)| contents.0 == from)
+    else {
+        return;
+    };
+    commands.spawn((
+        Name::new("Drag Ghost"),
+        DragGhost,
+        StateScoped(Screen::VoxelWorld),
+        ImageBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Px(DRAG_GHOST_SIZE),
+                height: Px(DRAG_GHOST_SIZE),
+                left,
+                top,
+                ..default()
+            },
+            image: image.clone(),
+            background_color: *color,
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+    ));
+}
+
+/// Split, quick move and drop act on the focused slot, which the mouse focuses by hovering it
+pub fn run_slot_actions(
+    input: Res<ActionState<UiAction>>,
+    focus: Res<UiFocus>,
+    slot_query: Query<&InventorySlotButton>,
+    mut held: ResMut<HeldSlot>,
+    mut inventory_query: Query<&mut Inventory, With<Player>>,
+    mut drops: EventWriter<DropSlot>,
+    voxels: Res<Blocks>,
+    voxel_data: Res<Assets<Block>>,
+) {
+    let Some(slot) = focus.entity.and_then(|entity| slot_query.get(entity).ok()) else {
+        return;
+    };
+    let Ok(mut inventory) = inventory_query.get_single_mut() else {
+        return;
+    };
+    if input.just_pressed(&UiAction::Split) {
+        // the new half is picked up, ready to be put down
+        *held = HeldSlot {
+            slot: inventory.split_slot(slot.0),
+            dragging: false,
+        };
+    }
+    if input.just_pressed(&UiAction::QuickMove) {
+        *held = HeldSlot::default();
+        inventory.quick_move(slot.0, |block: &BlockType| {
+            voxels.max_stack(&voxel_data, block)
+        });
+    }
+    if input.just_pressed(&UiAction::Drop) {
+        *held = HeldSlot::default();
+        drops.send(DropSlot {
+            index: slot.0,
+            count: 1,
+        });
+    }
+}
+
+pub fn sort_inventory(
+    button_query: InteractionQuery<&SortInventoryButton>,
+    mut held: ResMut<HeldSlot>,
+    mut inventory_query: Query<&mut Inventory, With<Player>>,
+    voxels: Res<Blocks>,
+    voxel_data: Res<Assets<Block>>,
+) {
+    for (interaction, _) in &button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            *held = HeldSlot::default();
+            for mut inventory in &mut inventory_query {
+                inventory.sort(|block: &BlockType| voxels.max_stack(&voxel_data, block));
+            }
+        }
+    }
+}
+
+/// Shows the held slot as pressed until it is put down
+pub fn show_held_slot(
+    held: Res<HeldSlot>,
+    mut slot_query: Query<(
+        Ref<Interaction>,
+        &InventorySlotButton,
+        &InteractionPalette,
+        &mut BackgroundColor,
+    )>,
+) {
+    for (interaction, slot, palette, mut background) in &mut slot_query {
+        if !held.is_changed() && !interaction.is_changed() {
+            continue;
+        }
+        *background = if held.slot == Some(slot.0) {
+            palette.pressed
+        } else {
+            palette.color(*interaction)
+        }
+        .into();
+    }
+}
+
+/// Back puts down the held slot, or closes the inventory when nothing is held
+pub fn back_out_of_inventory(
+    input: Res<ActionState<UiAction>>,
+    mut held: ResMut<HeldSlot>,
+    mut inventory_ui_query: Query<&mut Visibility, With<FullInventoryUI>>,
+) {
+    if !input.just_pressed(&UiAction::Back) {
+        return;
+    }
+    let Ok(mut visibility) = inventory_ui_query.get_single_mut() else {
+        return;
+    };
+    if *visibility == Visibility::Visible && held.slot.take().is_none() {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+/// Gameplay actions sharing inputs with [`UiAction`] are ignored while the full inventory is
+/// open, so moving around it does not move the player or break blocks
+pub fn capture_gameplay_input(
+    inventory_ui_query: Query<&Visibility, With<FullInventoryUI>>,
+    mut input_query: Query<&mut ActionState<PlayerAction>>,
+) {
+    if !inventory_ui_query
+        .get_single()
+        .is_ok_and(|visibility| *visibility == Visibility::Visible)
+    {
+        return;
+    }
+    for mut input in &mut input_query {
+        for action in [
+            PlayerAction::Hit,
+            PlayerAction::Place,
+            PlayerAction::Jump,
+            PlayerAction::Rotate,
+            PlayerAction::Move,
+            PlayerAction::MoveUp,
+            PlayerAction::MoveDown,
+            PlayerAction::MoveLeft,
+            PlayerAction::MoveRight,
+            PlayerAction::EnterHex,
+            PlayerAction::ExitChunk,
+            PlayerAction::ToolbarNext,
+            PlayerAction::ToolbarPrev,
+            PlayerAction::Blueprint,
+            PlayerAction::BlueprintNext,
+        ] {
+            input.consume(&action);
+        }
+    }
+}
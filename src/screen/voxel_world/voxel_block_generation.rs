@@ -1,9 +1,8 @@
 use bevy::asset::{Asset, AssetId, AssetIndex, AssetServer, Assets, Handle};
 use bevy::color::{Color, Srgba};
-use bevy::input::ButtonInput;
 use bevy::pbr::StandardMaterial;
 use bevy::prelude::*;
-use bevy::prelude::{AlphaMode, KeyCode, Query, Res, ResMut, Resource, With};
+use bevy::prelude::{AlphaMode, Query, Res, ResMut, Resource, With};
 use bevy::reflect::{Reflect, TypePath};
 use bevy::render::mesh::{Indices, Mesh};
 use bevy::render::render_asset::RenderAssetUsages;
@@ -14,18 +13,20 @@ use block_mesh::ndshape::{ConstShape, ConstShape3u32};
 use block_mesh::{
     greedy_quads, GreedyQuadsBuffer, MergeVoxel, Voxel, VoxelVisibility, RIGHT_HANDED_Y_UP_CONFIG,
 };
+use leafwing_input_manager::prelude::ActionState;
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
 use crate::game::main_character::Player;
-use crate::game::HexSelect;
+use crate::game::{HexSelect, PlayerAction};
 use crate::screen::hex_vox_util::HexId;
 use crate::screen::inventory::Inventory;
 use crate::screen::voxel_world::world::VoxelChunk;
 
+use super::item::DropItems;
 use super::voxel_util::WorldType;
-use super::voxels::{Block, BlockType, Blocks, VoxelBlock};
-use super::world::VoxelStore;
+use super::voxels::{Block, BlockSounds, BlockType, Blocks, VoxelBlock};
+use super::world::{loot::LootTable, VoxelStore};
 
 #[derive(TypePath, Asset)]
 pub struct VoxelMesh {
@@ -53,7 +54,7 @@ impl MergeVoxel for BlockType {
 }
 
 pub fn generate_voxel_mesh(voxel_block: &VoxelChunk) -> VoxelMesh {
-    let voxels: Vec<BlockType> = voxel_block.0.iter().cloned().collect();
+    let voxels: Vec<BlockType> = voxel_block.blocks().to_vec();
 
     let mut buffer = GreedyQuadsBuffer::new(voxels.len());
     greedy_quads(
@@ -150,17 +151,26 @@ impl VoxelDataMap {
         self.to_generate.insert(id);
         id
     }
+
+    /// the chunk a compressed voxel was made from
+    pub fn chunk(&self, id: VoxelBlockId) -> Option<&Handle<VoxelChunk>> {
+        self.id_to_data.get(&id)
+    }
 }
 
 pub fn compress(
-    input: Res<ButtonInput<KeyCode>>,
+    input: Query<&ActionState<PlayerAction>, With<Player>>,
     cursor: Query<&HexId, With<crate::screen::hex_map::cursor::Cursor>>,
     hexes: Query<(&HexId, &WorldType)>,
     asset_server: Res<AssetServer>,
     mut voxels: ResMut<VoxelDataMap>,
     mut inventory: Query<&mut Inventory, With<Player>>,
+    mut spilled: EventWriter<DropItems>,
 ) {
-    if input.just_pressed(KeyCode::KeyC) {
+    if input
+        .get_single()
+        .is_ok_and(|input| input.just_pressed(&PlayerAction::Compress))
+    {
         let mut world = WorldType::Empty;
         let cursor = cursor.single();
         for (id, new_world) in hexes.iter() {
@@ -172,7 +182,14 @@ pub fn compress(
             asset_server.load_with_settings(format!("chunk://{}", cursor), move |w| *w = world);
         for mut inventory in &mut inventory {
             let id = voxels.add_voxel(handle.clone());
-            inventory.add_resource(BlockType::Voxel(id), 1);
+            let block = BlockType::Voxel(id);
+            // compressed voxels have no .block file to set their stack size
+            if inventory.add_resource(block.clone(), 1, block.default_max_stack()) > 0 {
+                spilled.send(DropItems {
+                    block_type: block,
+                    count: 1,
+                });
+            }
         }
     }
 }
@@ -204,6 +221,12 @@ pub fn generate_dynamic_voxels(
             color: Color::linear_rgb(1., 0., 1.),
             solid: true,
             components: Vec::new(),
+            hardness: BlockType::Voxel(id).default_hardness(),
+            tier: BlockType::Voxel(id).default_tier(),
+            tool: None,
+            drops: LootTable::single(BlockType::Voxel(id)),
+            sounds: BlockSounds::default(),
+            max_stack: BlockType::Voxel(id).default_max_stack(),
         });
         voxel_mapping.id_to_block.insert(id, block.clone());
         voxels.set(BlockType::Voxel(id), block);
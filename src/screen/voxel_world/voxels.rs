@@ -10,12 +10,18 @@ use bevy::{
 // };
 use serde::{Deserialize, Serialize};
 use serde_big_array::Array;
-use std::{array, hash::Hash, sync::Arc};
+use std::{array, sync::Arc};
 use strum::IntoEnumIterator;
 
-use crate::screen::hex_vox_util::MapDirection;
+use crate::{
+    game::{assets::SfxKey, audio::sfx::PlaySfx},
+    screen::hex_vox_util::MapDirection,
+};
 
-use super::voxel_block_generation::{generate_voxel_mesh, VoxelBlockId};
+use super::{
+    voxel_block_generation::{generate_voxel_mesh, VoxelBlockId},
+    world::loot::LootTable,
+};
 
 pub struct VoxelPlugin;
 
@@ -33,6 +39,14 @@ pub struct Blocks {
 }
 
 impl Blocks {
+    /// No blocks, they are added with [`Blocks::set`]
+    #[cfg(test)]
+    pub fn empty() -> Blocks {
+        Blocks {
+            type_to_asset: HashMap::new(),
+        }
+    }
+
     pub fn get(&self, block: BlockType) -> Handle<Block> {
         self.type_to_asset
             .get(&block)
@@ -43,6 +57,14 @@ impl Blocks {
     pub fn set(&mut self, block: BlockType, handle: Handle<Block>) {
         self.type_to_asset.insert(block, handle);
     }
+
+    /// How many of `block` fit in one inventory slot, from its `.block` file once loaded
+    pub fn max_stack(&self, blocks: &Assets<Block>, block: &BlockType) -> u32 {
+        self.type_to_asset
+            .get(block)
+            .and_then(|handle| blocks.get(handle.id()))
+            .map_or_else(|| block.default_max_stack(), Block::max_stack)
+    }
 }
 
 impl FromWorld for Blocks {
@@ -56,9 +78,10 @@ impl FromWorld for Blocks {
     }
 }
 
+/// `Id` is only changed to read `.block` files written before orientations left [`BlockType`]
 #[derive(Serialize, Deserialize)]
-struct BlockAsset {
-    id: BlockType,
+struct BlockAsset<Id = BlockType> {
+    id: Id,
     flags: Vec<BlockFlags>,
     mesh: Option<String>,
     texture: String,
@@ -67,6 +90,40 @@ struct BlockAsset {
     solid: bool,
     #[serde(default)]
     components: Vec<BlockLogic>,
+    #[serde(default)]
+    hardness: Option<f32>,
+    #[serde(default)]
+    tier: Option<u8>,
+    #[serde(default)]
+    tool: Option<ToolStats>,
+    #[serde(default)]
+    drops: Option<LootTable>,
+    #[serde(default)]
+    sounds: BlockSounds,
+    /// how many fit in one inventory slot
+    #[serde(default)]
+    max_stack: Option<u32>,
+}
+
+impl BlockAsset<LegacyBlockType> {
+    fn migrate(self) -> BlockAsset {
+        let (id, _) = self.id.migrate();
+        BlockAsset {
+            id,
+            flags: self.flags,
+            mesh: self.mesh,
+            texture: self.texture,
+            color: self.color,
+            solid: self.solid,
+            components: self.components,
+            hardness: self.hardness,
+            tier: self.tier,
+            tool: self.tool,
+            drops: self.drops,
+            sounds: self.sounds,
+            max_stack: self.max_stack,
+        }
+    }
 }
 
 fn default_solid() -> bool {
@@ -82,6 +139,57 @@ pub struct Block {
     pub color: Color,
     pub solid: bool,
     pub components: Vec<BlockLogic>,
+    pub hardness: f32,
+    pub tier: u8,
+    pub tool: Option<ToolStats>,
+    pub drops: LootTable,
+    pub sounds: BlockSounds,
+    pub max_stack: u32,
+}
+
+/// The sounds a block makes, any left empty fall back to the shared ones
+#[derive(Debug, Serialize, Deserialize, Clone, Default, Reflect)]
+pub struct BlockSounds {
+    #[serde(default)]
+    pub hit: Vec<SfxKey>,
+    #[serde(default)]
+    pub broken: Vec<SfxKey>,
+    #[serde(default)]
+    pub placed: Vec<SfxKey>,
+    /// played now and then while the block is in the world
+    #[serde(default)]
+    pub ambient: Vec<SfxKey>,
+}
+
+impl BlockSounds {
+    pub fn hit(&self) -> PlaySfx {
+        if self.hit.is_empty() {
+            PlaySfx::BlockHit
+        } else {
+            PlaySfx::OneOf(self.hit.clone())
+        }
+    }
+
+    pub fn broken(&self) -> PlaySfx {
+        PlaySfx::OneOf(self.broken.clone())
+    }
+
+    pub fn placed(&self) -> PlaySfx {
+        if self.placed.is_empty() {
+            PlaySfx::RandomStep
+        } else {
+            PlaySfx::OneOf(self.placed.clone())
+        }
+    }
+}
+
+/// What an item does when held while mining
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Reflect)]
+pub struct ToolStats {
+    /// the highest [`Block::tier`] this tool can mine
+    pub tier: u8,
+    /// how much breaking progress is made per second on a block with a hardness of 1
+    pub speed: f32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, Reflect)]
@@ -91,6 +199,7 @@ pub enum BlockLogic {
     ScoreGive,
     Piston(u8),
     Conveyor,
+    Generator,
 }
 
 impl Block {
@@ -127,6 +236,37 @@ impl Block {
         self.melt().is_some()
     }
 
+    pub fn falls(&self) -> bool {
+        self.flags.contains(&BlockFlags::Falls)
+    }
+
+    pub fn hardness(&self) -> f32 {
+        self.hardness
+    }
+
+    /// how many of this block fit in one inventory slot
+    pub fn max_stack(&self) -> u32 {
+        self.max_stack
+    }
+
+    /// the tool tier needed to mine this block, 0 can be mined by hand
+    pub fn required_tier(&self) -> u8 {
+        self.tier
+    }
+
+    pub fn tool(&self) -> Option<ToolStats> {
+        self.tool
+    }
+
+    pub fn drops(&self) -> &LootTable {
+        &self.drops
+    }
+
+    /// tools are held not placed
+    pub fn can_place(&self) -> bool {
+        self.tool.is_none()
+    }
+
     pub fn mesh(&self) -> Handle<Mesh> {
         self.mesh.clone()
     }
@@ -143,9 +283,13 @@ impl Block {
                 BlockLogic::Melter => entity.insert(Melter),
                 BlockLogic::ScoreGive => entity.insert(ScoreGive),
                 BlockLogic::Piston(power) => entity.insert(Piston(*power as f32)),
-                BlockLogic::Conveyor => entity.insert(Conveyor),
+                BlockLogic::Conveyor => entity.insert(Conveyor::default()),
+                BlockLogic::Generator => entity.insert(super::world::energy::Generator),
             };
         }
+        if !self.sounds.ambient.is_empty() {
+            entity.insert(AmbientSound::new(self.sounds.ambient.clone()));
+        }
     }
 }
 
@@ -155,13 +299,13 @@ impl Block {
     Reflect,
     Clone,
     strum_macros::EnumIter,
-    strum_macros::EnumDiscriminants,
     Debug,
     Component,
+    PartialEq,
     Eq,
+    Hash,
     Default,
 )]
-#[strum_discriminants(derive(Hash))]
 pub enum BlockType {
     #[default]
     Air,
@@ -172,7 +316,7 @@ pub enum BlockType {
     BedRock,
     Score,
     Furnace,
-    Drill(MapDirection),
+    Drill,
     Sand,
     Glass,
     CobaltOre,
@@ -182,10 +326,26 @@ pub enum BlockType {
     Sodium,
     Potassium,
     Magnesium,
-    Piston(MapDirection),
-    PistonL2(MapDirection),
-    Conveyor(MapDirection),
+    Piston,
+    PistonL2,
+    Conveyor,
     Voxel(VoxelBlockId),
+    IronPickaxe,
+    CopperPickaxe,
+    CobaltPickaxe,
+    Lever,
+    Button,
+    PressurePlate,
+    ItemDetector,
+    Wire,
+    NotGate,
+    AndGate,
+    Generator,
+    Battery,
+    Cable,
+    Pipe,
+    Tank,
+    CastingBasin,
 }
 
 const VOXEL_DIVISION_FACTOR: usize = 16usize.pow(3);
@@ -201,51 +361,124 @@ impl Default for VoxelBlock {
 
 #[test]
 fn hash_test() {
-    use std::hash::Hasher;
+    use std::hash::{Hash, Hasher};
     let mut hasher_two = std::hash::DefaultHasher::new();
     let mut hasher_one = std::hash::DefaultHasher::new();
-    BlockType::Piston(MapDirection::Up).hash(&mut hasher_one);
-    BlockType::Piston(MapDirection::Down).hash(&mut hasher_two);
-    assert!(BlockType::Piston(MapDirection::Up) == BlockType::Piston(MapDirection::Down));
+    let (up, _) = LegacyBlockType::Piston(MapDirection::Up).migrate();
+    let (down, direction) = LegacyBlockType::Piston(MapDirection::Down).migrate();
+    up.hash(&mut hasher_one);
+    down.hash(&mut hasher_two);
+    assert!(up == down);
+    assert_eq!(direction, MapDirection::Down);
     assert_eq!(hasher_one.finish(), hasher_two.finish())
 }
 
-impl Hash for BlockType {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        BlockTypeDiscriminants::from(self).hash(state);
-        if let BlockType::Voxel(id) = self {
-            id.hash(state);
-        }
-    }
+#[test]
+fn block_sounds_fall_back_to_shared_sets() {
+    let generator: BlockAsset = ron::from_str(
+        r#"(
+            id: Generator,
+            flags: [],
+            mesh: None,
+            texture: "images/voxels/refined_iron.png",
+            color: Srgba((red: 0.3, green: 0.3, blue: 0.3, alpha: 1.0)),
+            components: [Generator],
+            sounds: (ambient: [Melt]),
+        )"#,
+    )
+    .unwrap();
+    assert_eq!(generator.sounds.ambient, vec![SfxKey::Melt]);
+    let cable: BlockAsset = ron::from_str(
+        r#"(
+            id: Cable,
+            flags: [],
+            mesh: None,
+            texture: "images/voxels/refined_copper.png",
+            color: Srgba((red: 0.9, green: 0.8, blue: 0.1, alpha: 1.0)),
+        )"#,
+    )
+    .unwrap();
+    assert!(matches!(cable.sounds.hit(), PlaySfx::BlockHit));
+    assert!(matches!(cable.sounds.placed(), PlaySfx::RandomStep));
 }
 
-impl PartialEq for BlockType {
-    fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (BlockType::Voxel(id), BlockType::Voxel(other)) => id == other,
-            (_, _) => BlockTypeDiscriminants::from(self) == BlockTypeDiscriminants::from(other),
-        }
-    }
+/// [`BlockType`] from before orientations were stored beside it, only used to read old saves
+#[derive(Deserialize, Clone, Debug)]
+pub(crate) enum LegacyBlockType {
+    Air,
+    Stone,
+    Coal,
+    IronOre,
+    IronBlock,
+    BedRock,
+    Score,
+    Furnace,
+    Drill(MapDirection),
+    Sand,
+    Glass,
+    CobaltOre,
+    CobaltBlock,
+    CopperOre,
+    CopperBlock,
+    Sodium,
+    Potassium,
+    Magnesium,
+    Piston(MapDirection),
+    PistonL2(MapDirection),
+    Conveyor(MapDirection),
+    Voxel(VoxelBlockId),
+    IronPickaxe,
+    CopperPickaxe,
+    CobaltPickaxe,
 }
 
-impl BlockType {
-    pub fn direction(&self) -> MapDirection {
+impl LegacyBlockType {
+    /// splits the old block into its kind and the way it was facing
+    pub(crate) fn migrate(self) -> (BlockType, MapDirection) {
+        let up = MapDirection::default();
         match self {
-            BlockType::Piston(d)
-            | BlockType::PistonL2(d)
-            | BlockType::Drill(d)
-            | BlockType::Conveyor(d) => *d,
-            _ => MapDirection::Up,
+            LegacyBlockType::Air => (BlockType::Air, up),
+            LegacyBlockType::Stone => (BlockType::Stone, up),
+            LegacyBlockType::Coal => (BlockType::Coal, up),
+            LegacyBlockType::IronOre => (BlockType::IronOre, up),
+            LegacyBlockType::IronBlock => (BlockType::IronBlock, up),
+            LegacyBlockType::BedRock => (BlockType::BedRock, up),
+            LegacyBlockType::Score => (BlockType::Score, up),
+            LegacyBlockType::Furnace => (BlockType::Furnace, up),
+            LegacyBlockType::Drill(direction) => (BlockType::Drill, direction),
+            LegacyBlockType::Sand => (BlockType::Sand, up),
+            LegacyBlockType::Glass => (BlockType::Glass, up),
+            LegacyBlockType::CobaltOre => (BlockType::CobaltOre, up),
+            LegacyBlockType::CobaltBlock => (BlockType::CobaltBlock, up),
+            LegacyBlockType::CopperOre => (BlockType::CopperOre, up),
+            LegacyBlockType::CopperBlock => (BlockType::CopperBlock, up),
+            LegacyBlockType::Sodium => (BlockType::Sodium, up),
+            LegacyBlockType::Potassium => (BlockType::Potassium, up),
+            LegacyBlockType::Magnesium => (BlockType::Magnesium, up),
+            LegacyBlockType::Piston(direction) => (BlockType::Piston, direction),
+            LegacyBlockType::PistonL2(direction) => (BlockType::PistonL2, direction),
+            LegacyBlockType::Conveyor(direction) => (BlockType::Conveyor, direction),
+            LegacyBlockType::Voxel(id) => (BlockType::Voxel(id), up),
+            LegacyBlockType::IronPickaxe => (BlockType::IronPickaxe, up),
+            LegacyBlockType::CopperPickaxe => (BlockType::CopperPickaxe, up),
+            LegacyBlockType::CobaltPickaxe => (BlockType::CobaltPickaxe, up),
         }
     }
+}
 
-    pub fn set_direction(&mut self, direction: MapDirection) {
-        match self {
-            BlockType::Piston(to) | BlockType::PistonL2(to) | BlockType::Drill(to) => {
-                *to = direction;
-            }
-            _ => {}
-        }
+impl BlockType {
+    /// blocks that keep the orientation they were placed with, the rest always face up
+    pub fn is_directional(&self) -> bool {
+        matches!(
+            self,
+            BlockType::Piston
+                | BlockType::PistonL2
+                | BlockType::Drill
+                | BlockType::Conveyor
+                | BlockType::ItemDetector
+                | BlockType::NotGate
+                | BlockType::AndGate
+        )
     }
 
     pub fn path(&self) -> &'static str {
@@ -262,16 +495,32 @@ impl BlockType {
             BlockType::BedRock => "blocks/bedrock.block",
             BlockType::Score => "blocks/score.block",
             BlockType::Furnace => "blocks/furnace.block",
-            BlockType::Drill(_) => "blocks/drill.block",
+            BlockType::Drill => "blocks/drill.block",
             BlockType::Sand => "blocks/sand.block",
             BlockType::Glass => "blocks/glass.block",
             BlockType::Sodium => "blocks/sodium.block",
             BlockType::Potassium => "blocks/potassium.block",
             BlockType::Magnesium => "blocks/magnesium.block",
-            BlockType::Piston(_) => "blocks/piston.block",
-            BlockType::PistonL2(_) => "blocks/piston_l2.block",
+            BlockType::Piston => "blocks/piston.block",
+            BlockType::PistonL2 => "blocks/piston_l2.block",
             BlockType::Voxel(_) => "blocks/voxel.block",
-            BlockType::Conveyor(_) => "blocks/conveyor.block",
+            BlockType::Conveyor => "blocks/conveyor.block",
+            BlockType::IronPickaxe => "blocks/pickaxe_iron.block",
+            BlockType::CopperPickaxe => "blocks/pickaxe_copper.block",
+            BlockType::CobaltPickaxe => "blocks/pickaxe_cobalt.block",
+            BlockType::Lever => "blocks/lever.block",
+            BlockType::Button => "blocks/button.block",
+            BlockType::PressurePlate => "blocks/pressure_plate.block",
+            BlockType::ItemDetector => "blocks/item_detector.block",
+            BlockType::Wire => "blocks/wire.block",
+            BlockType::NotGate => "blocks/not_gate.block",
+            BlockType::AndGate => "blocks/and_gate.block",
+            BlockType::Generator => "blocks/generator.block",
+            BlockType::Battery => "blocks/battery.block",
+            BlockType::Cable => "blocks/cable.block",
+            BlockType::Pipe => "blocks/pipe.block",
+            BlockType::Tank => "blocks/tank.block",
+            BlockType::CastingBasin => "blocks/casting_basin.block",
         }
     }
 
@@ -285,7 +534,7 @@ impl BlockType {
             BlockType::BedRock => Color::srgba(0.3, 0.3, 0.3, 1.0), // Dark Gray
             BlockType::Score => Color::srgba(1.0, 0.84, 0.0, 1.0), // Gold
             BlockType::Furnace => Color::srgba(0.8, 0.4, 0.0, 1.0), // Orange-Red
-            BlockType::Drill(_) => Color::srgba(0.5, 0.0, 0.5, 1.0), // Purple
+            BlockType::Drill => Color::srgba(0.5, 0.0, 0.5, 1.0), // Purple
             BlockType::Sand => Color::srgba(0.94, 0.87, 0.69, 1.0), // Yellow
             BlockType::Glass => Color::srgba(0.8, 1.0, 1.0, 1.0), // Cyan
             BlockType::CobaltOre => Color::srgba(0.0, 0.0, 0.8, 1.0), // Blue
@@ -295,10 +544,90 @@ impl BlockType {
             BlockType::Sodium => Color::srgba(0.6, 0.8, 0.2, 1.0), // Yellow-Green
             BlockType::Potassium => Color::srgba(1.0, 0.0, 1.0, 1.0), // Magenta
             BlockType::Magnesium => Color::srgba(1.0, 0.75, 0.8, 1.0), // Pink
-            BlockType::Piston(_) => Color::srgba(0.5, 0.5, 0.0, 1.0), // Olive
-            BlockType::PistonL2(_) => Color::srgba(0.25, 0.25, 0.0, 1.0), // Olive / 2.?
+            BlockType::Piston => Color::srgba(0.5, 0.5, 0.0, 1.0), // Olive
+            BlockType::PistonL2 => Color::srgba(0.25, 0.25, 0.0, 1.0), // Olive / 2.?
             BlockType::Voxel(_) => Color::srgba(1.0, 1.0, 1.0, 1.0), // White
-            BlockType::Conveyor(_) => Color::srgba(1., 1., 1., 1.), //White
+            BlockType::Conveyor => Color::srgba(1., 1., 1., 1.), //White
+            BlockType::IronPickaxe => Color::srgba(0.7, 0.7, 0.7, 1.0), // Silver
+            BlockType::CopperPickaxe => Color::srgba(1.0, 0.65, 0.0, 1.0), // Orange
+            BlockType::CobaltPickaxe => Color::srgba(0.0, 0.0, 0.5, 1.0), // Navy
+            BlockType::Lever => Color::srgba(0.6, 0.4, 0.2, 1.0), // Brown
+            BlockType::Button => Color::srgba(0.6, 0.6, 0.6, 1.0), // Light Gray
+            BlockType::PressurePlate => Color::srgba(0.4, 0.4, 0.4, 1.0), // Gray
+            BlockType::ItemDetector => Color::srgba(0.3, 0.7, 0.9, 1.0), // Sky Blue
+            BlockType::Wire => Color::srgba(0.8, 0.1, 0.1, 1.0), // Red
+            BlockType::NotGate => Color::srgba(0.9, 0.3, 0.3, 1.0), // Light Red
+            BlockType::AndGate => Color::srgba(0.9, 0.6, 0.3, 1.0), // Light Orange
+            BlockType::Generator => Color::srgba(0.3, 0.3, 0.3, 1.0), // Charcoal
+            BlockType::Battery => Color::srgba(0.2, 0.6, 0.2, 1.0), // Dark Green
+            BlockType::Cable => Color::srgba(0.9, 0.8, 0.1, 1.0), // Yellow
+            BlockType::Pipe => Color::srgba(0.6, 0.6, 0.7, 1.0), // Steel
+            BlockType::Tank => Color::srgba(0.4, 0.5, 0.6, 1.0), // Slate
+            BlockType::CastingBasin => Color::srgba(0.5, 0.3, 0.2, 1.0), // Clay
+        }
+    }
+
+    /// used when a .block file does not set its own hardness
+    pub fn default_hardness(&self) -> f32 {
+        match self {
+            BlockType::Air => 0.,
+            BlockType::Sand | BlockType::Glass => 0.5,
+            BlockType::Stone
+            | BlockType::Coal
+            | BlockType::BedRock
+            | BlockType::Sodium
+            | BlockType::Potassium
+            | BlockType::Magnesium
+            | BlockType::Conveyor
+            | BlockType::Voxel(_) => 1.,
+            BlockType::IronOre => 1.5,
+            BlockType::CopperOre => 2.,
+            BlockType::CobaltOre => 2.5,
+            BlockType::IronBlock | BlockType::CobaltBlock | BlockType::CopperBlock => 2.,
+            BlockType::Score
+            | BlockType::Furnace
+            | BlockType::Drill
+            | BlockType::Piston
+            | BlockType::PistonL2 => 3.,
+            BlockType::IronPickaxe | BlockType::CopperPickaxe | BlockType::CobaltPickaxe => 0.5,
+            BlockType::Lever
+            | BlockType::Button
+            | BlockType::PressurePlate
+            | BlockType::ItemDetector
+            | BlockType::Wire
+            | BlockType::NotGate
+            | BlockType::AndGate
+            | BlockType::Cable
+            | BlockType::Pipe => 0.5,
+            BlockType::Generator
+            | BlockType::Battery
+            | BlockType::Tank
+            | BlockType::CastingBasin => 2.,
+        }
+    }
+
+    /// used when a .block file does not set its own tier
+    pub fn default_tier(&self) -> u8 {
+        match self {
+            BlockType::CopperOre => 1,
+            BlockType::CobaltOre => 2,
+            _ => 0,
+        }
+    }
+
+    /// used when a .block file does not set its own stack size, or before the files load
+    pub fn default_max_stack(&self) -> u32 {
+        match self {
+            BlockType::Voxel(_) => 16,
+            _ => 64,
+        }
+    }
+
+    /// added to whatever flags a .block file lists, so older files keep their behaviour
+    pub fn default_flags(&self) -> Vec<BlockFlags> {
+        match self {
+            BlockType::Sand => vec![BlockFlags::Falls],
+            _ => Vec::new(),
         }
     }
 }
@@ -308,6 +637,8 @@ pub enum BlockFlags {
     NoMine,
     CanMelt(BlockType),
     Fuel,
+    /// drops when the voxel below it is not solid
+    Falls,
 }
 
 struct BlockLoader {
@@ -336,7 +667,12 @@ impl AssetLoader for BlockLoader {
         async {
             let mut str = String::default();
             reader.read_to_string(&mut str).await?;
-            let block = ron::from_str::<BlockAsset>(&str)?;
+            let block = match ron::from_str::<BlockAsset>(&str) {
+                Ok(block) => block,
+                Err(error) => ron::from_str::<BlockAsset<LegacyBlockType>>(&str)
+                    .map(BlockAsset::migrate)
+                    .map_err(|_| error)?,
+            };
 
             let mesh = match &block.id {
                 _ => {
@@ -348,9 +684,16 @@ impl AssetLoader for BlockLoader {
                 }
             };
 
+            let mut flags = block.flags;
+            for flag in block.id.default_flags() {
+                if !flags.contains(&flag) {
+                    flags.push(flag);
+                }
+            }
+
             Ok(Block {
                 id: block.id,
-                flags: block.flags,
+                flags,
                 mesh,
                 material: {
                     let material = StandardMaterial {
@@ -365,6 +708,20 @@ impl AssetLoader for BlockLoader {
                 color: block.color,
                 solid: block.solid,
                 components: block.components,
+                hardness: block
+                    .hardness
+                    .unwrap_or_else(|| block.id.default_hardness()),
+                tier: block.tier.unwrap_or_else(|| block.id.default_tier()),
+                tool: block.tool,
+                drops: block
+                    .drops
+                    .unwrap_or_else(|| LootTable::default_for(&block.id)),
+                sounds: block.sounds,
+                // a stack of nothing would never hold anything
+                max_stack: block
+                    .max_stack
+                    .unwrap_or_else(|| block.id.default_max_stack())
+                    .max(1),
             })
         }
     }
@@ -1,20 +1,29 @@
 use crate::{
-    game::{audio::sfx::PlaySfx, main_character::Player, HexSelect, PlayerAction},
+    game::{assets::SfxKey, audio::sfx::PlaySfx, main_character::Player, HexSelect, PlayerAction},
     screen::{
         hex_vox_util::MapDirection,
         inventory::Inventory,
         voxel_world::{
+            item::{spawn_item, DropItems},
             voxel_util::VoxelPlayer,
-            voxels::{Block, BlockType, Blocks},
+            voxels::{Block, BlockType, Blocks, ToolStats},
         },
         Screen,
     },
 };
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
-use leafwing_input_manager::{action_state::ActionData, prelude::ActionState};
+use leafwing_input_manager::prelude::ActionState;
 
-use super::{VoxelChunk, VoxelId};
+use super::{
+    block_changes::{ChangeCause, SetBlock},
+    blueprint::blueprint_inactive,
+    ghost::{Ghost, GhostPreview},
+    loot::DropSettings,
+    multi_block::MultiBlockOrigins,
+    signal::{is_switch, UseBlock},
+    VoxelChunk, VoxelId,
+};
 
 #[derive(Resource, Reflect, Default)]
 #[reflect(Resource)]
@@ -33,17 +42,25 @@ pub(crate) fn block_breaking_plugin(app: &mut App) {
         app.register_type::<BlockBreakDebugSettings>();
         app.add_systems(Update, draw_debug);
     }
-    app.add_systems(Update, block_placing.run_if(in_state(Screen::VoxelWorld)))
+    app.init_resource::<PlacementRotation>()
+        .add_systems(
+            Update,
+            (rotate_placement, placement_preview, block_placing)
+                .chain()
+                .run_if(in_state(Screen::VoxelWorld).and_then(blueprint_inactive)),
+        )
+        .add_systems(OnExit(Screen::VoxelWorld), reset_rotation)
         .add_systems(
             Update,
             (
                 break_block,
-                scail_breaking_block,
                 unbreak_block,
                 pickup_block,
+                update_break_progress,
             )
                 .chain(),
-        );
+        )
+        .add_systems(OnEnter(Screen::VoxelWorld), spawn_break_progress);
 }
 
 fn draw_debug(
@@ -76,21 +93,51 @@ fn unbreak_block(
     }
 }
 
+/// how much progress a click with no tool makes on a block with a hardness of 1
+const HAND_POWER: f32 = 0.5;
+
+fn held_tool(
+    inventory: &Inventory,
+    blocks: &Blocks,
+    block_data: &Assets<Block>,
+) -> Option<ToolStats> {
+    inventory.get_selected_block().and_then(|held| {
+        let held = blocks.get(held);
+        block_data.get(held.id()).and_then(Block::tool)
+    })
+}
+
 fn break_block(
     mut commands: Commands,
-    input: Query<&ActionState<PlayerAction>>,
+    time: Res<Time>,
+    input: Query<(&ActionState<PlayerAction>, &Inventory), With<Player>>,
     physics: Res<RapierContext>,
     player: Query<(&Parent, &GlobalTransform), With<VoxelPlayer>>,
-    mut voxels: Query<Option<&mut Breaking>, With<VoxelId>>,
+    mut voxels: Query<(&VoxelId, Option<&mut Breaking>)>,
+    selected: Res<HexSelect>,
+    chunk_data: Res<Assets<VoxelChunk>>,
+    blocks: Res<Blocks>,
+    block_data: Res<Assets<Block>>,
 ) {
-    let Ok(input) = input.get_single() else {
+    let Ok((input, inventory)) = input.get_single() else {
         warn!("no player");
         return;
     };
 
-    if !input.just_pressed(&PlayerAction::Hit) {
+    let tool = held_tool(inventory, &blocks, &block_data);
+
+    // tools break blocks while held, hands need a click per hit
+    let power = match tool {
+        Some(tool) if input.pressed(&PlayerAction::Hit) => tool.speed * time.delta_seconds(),
+        None if input.just_pressed(&PlayerAction::Hit) => HAND_POWER,
+        _ => return,
+    };
+    let tier = tool.map(|tool| tool.tier).unwrap_or(0);
+
+    let Some(chunk) = chunk_data.get(selected.chunk.id()) else {
         return;
-    }
+    };
+
     for (ignore, player) in &player {
         if let Some((hit, _)) = physics.cast_ray(
             player.translation(),
@@ -99,16 +146,30 @@ fn break_block(
             false,
             QueryFilter::new().exclude_rigid_body(ignore.get()),
         ) {
-            commands.trigger(PlaySfx::BlockHit);
-            match voxels.get_mut(hit) {
-                Ok(None) => {
-                    commands.entity(hit).insert(Breaking(0.5));
+            let Ok((id, breaking)) = voxels.get_mut(hit) else {
+                error!("rays should only hit voxels");
+                continue;
+            };
+            let block = blocks.get(chunk.get(id.0));
+            let Some(block) = block_data.get(block.id()) else {
+                continue;
+            };
+            if !block.can_mine() || block.required_tier() > tier {
+                if input.just_pressed(&PlayerAction::Hit) {
+                    commands.trigger(PlaySfx::Key(SfxKey::NoProgress).at(id.0.as_vec3()));
                 }
-                Ok(Some(mut breaking)) => {
-                    breaking.0 += 0.5;
+                continue;
+            }
+            if input.just_pressed(&PlayerAction::Hit) {
+                commands.trigger(block.sounds.hit().at(id.0.as_vec3()));
+            }
+            let progress = power / block.hardness().max(0.01);
+            match breaking {
+                None => {
+                    commands.entity(hit).insert(Breaking(progress));
                 }
-                Err(_) => {
-                    error!("rays should only hit voxels");
+                Some(mut breaking) => {
+                    breaking.0 += progress;
                 }
             }
         }
@@ -118,59 +179,246 @@ fn break_block(
 fn pickup_block(
     mut commands: Commands,
     mut player: Query<&mut Inventory, With<Player>>,
-    blocks: Query<(Entity, &Breaking, &VoxelId), Changed<Breaking>>,
+    broken: Query<(Entity, &Breaking, &VoxelId), Changed<Breaking>>,
     selected: Res<HexSelect>,
-    mut chunk_data: ResMut<Assets<VoxelChunk>>,
+    chunk_data: Res<Assets<VoxelChunk>>,
+    mut set_block: EventWriter<SetBlock>,
+    blocks: Res<Blocks>,
+    block_data: Res<Assets<Block>>,
+    settings: Res<DropSettings>,
+    mut origins: ResMut<MultiBlockOrigins>,
+    mut spilled: EventWriter<DropItems>,
 ) {
-    for (entity, state, id) in &blocks {
-        if state.0 >= 0.55 {
-            let Some(chunk) = chunk_data.get_mut(selected.chunk.id()) else {
+    let tier = player
+        .get_single()
+        .ok()
+        .and_then(|inventory| held_tool(inventory, &blocks, &block_data))
+        .map(|tool| tool.tier)
+        .unwrap_or(0);
+    let mut rng = rand::thread_rng();
+    for (entity, state, id) in &broken {
+        if state.0 >= 1. {
+            let Some(chunk) = chunk_data.get(selected.chunk.id()) else {
                 continue;
             };
-            let out = chunk.set(id.0, BlockType::Air);
+            let out = chunk.get(id.0);
             if out == BlockType::Air {
                 warn!("Removed Air");
                 continue;
             };
-            for mut inventory in &mut player {
-                inventory.add_resource(out.clone(), 1);
+            // stop it being picked up again before the change is applied
+            commands.entity(entity).remove::<Breaking>();
+            set_block.send(SetBlock {
+                pos: id.0,
+                block: BlockType::Air,
+                orientation: MapDirection::default(),
+                cause: ChangeCause::Broken,
+            });
+            // machines give back what they were built from
+            let drops = if let Some(origin) = origins.take(id.0) {
+                origin.refund()
+            } else {
+                let data = blocks.get(out);
+                let Some(data) = block_data.get(data.id()) else {
+                    continue;
+                };
+                data.drops().roll(tier, &mut rng)
+            };
+            for (drop, count) in drops {
+                if settings.as_items {
+                    for _ in 0..count {
+                        spawn_item(
+                            drop.clone(),
+                            &block_data,
+                            &blocks,
+                            id.0.as_vec3(),
+                            &mut commands,
+                        );
+                    }
+                } else {
+                    let max_stack = blocks.max_stack(&block_data, &drop);
+                    for mut inventory in &mut player {
+                        let overflow = inventory.add_resource(drop.clone(), count, max_stack);
+                        if overflow > 0 {
+                            spilled.send(DropItems {
+                                block_type: drop.clone(),
+                                count: overflow,
+                            });
+                        }
+                    }
+                }
             }
-            commands.entity(entity).despawn_recursive();
         }
     }
 }
 
-fn scail_breaking_block(
-    mut blocks: Query<&mut Transform>,
-    mut changed: Query<(Entity, &Breaking), Changed<Breaking>>,
-    mut removed: RemovedComponents<Breaking>,
+#[derive(Component)]
+struct BreakProgressBar;
+
+fn spawn_break_progress(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Break Progress"),
+            StateScoped(Screen::VoxelWorld),
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(45.),
+                    top: Val::Percent(53.),
+                    width: Val::Percent(10.),
+                    height: Val::Px(6.),
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.6)),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+        ))
+        .with_children(|bar| {
+            bar.spawn((
+                Name::new("Break Progress Fill"),
+                BreakProgressBar,
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(0.),
+                        height: Val::Percent(100.),
+                        ..Default::default()
+                    },
+                    background_color: BackgroundColor(Color::srgb(0.9, 0.9, 0.9)),
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+/// shows the progress of the most broken block
+fn update_break_progress(
+    breaking: Query<&Breaking>,
+    mut bar: Query<(&Parent, &mut Style), With<BreakProgressBar>>,
+    mut background: Query<&mut Visibility>,
 ) {
-    for (block, breaking) in &mut changed {
-        let Ok(mut transform) = blocks.get_mut(block) else {
-            warn!("Breaking block has no transform");
-            continue;
-        };
-        // max is so 0 scale is not possible;
-        transform.scale = Vec3::splat(1. - breaking.0).max(Vec3::splat(0.01));
+    let progress = breaking
+        .iter()
+        .map(|breaking| breaking.0)
+        .fold(0., f32::max)
+        .min(1.);
+    for (parent, mut style) in &mut bar {
+        style.width = Val::Percent(progress * 100.);
+        if let Ok(mut visibility) = background.get_mut(parent.get()) {
+            *visibility = if progress > 0. {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
     }
-    for block in removed.read() {
-        let Ok(mut transform) = blocks.get_mut(block) else {
-            continue;
-        };
-        transform.scale = Vec3::ONE;
+}
+
+/// Resource for the direction picked with [`PlayerAction::Rotate`]
+#[derive(Resource, Default)]
+pub struct PlacementRotation {
+    /// `None` follows the face being looked at
+    pub picked: Option<MapDirection>,
+    /// the direction the presses started from, coming back round to it follows the face again
+    from: MapDirection,
+}
+
+fn reset_rotation(mut rotation: ResMut<PlacementRotation>) {
+    rotation.picked = None;
+}
+
+/// The voxel being looked at, where the selected block would go and which way it would face
+fn placement_target(
+    transform: &GlobalTransform,
+    physics: &RapierContext,
+    blocks: &Query<&VoxelId>,
+    rotation: &PlacementRotation,
+) -> Option<(IVec3, VoxelId, MapDirection)> {
+    let (hit, normal) = physics.cast_ray_and_get_normal(
+        transform.translation(),
+        transform.forward().as_vec3(),
+        6.,
+        false,
+        QueryFilter::only_fixed(),
+    )?;
+    let old = blocks.get(hit).ok()?;
+    let id = vec3_to_voxel_id(normal.normal) + *old;
+    if !id.in_chunk() {
+        return None;
     }
+    let up = rotation
+        .picked
+        .unwrap_or_else(|| normal_to_direction(normal.normal));
+    Some((old.0, id, up))
+}
+
+fn rotate_placement(
+    player: Query<&ActionState<PlayerAction>, With<Player>>,
+    transform: Query<&GlobalTransform, With<VoxelPlayer>>,
+    physics: Res<RapierContext>,
+    blocks: Query<&VoxelId>,
+    mut rotation: ResMut<PlacementRotation>,
+) {
+    let (Ok(input), Ok(transform)) = (player.get_single(), transform.get_single()) else {
+        return;
+    };
+    if !input.just_pressed(&PlayerAction::Rotate) {
+        return;
+    }
+    rotation.picked = match rotation.picked {
+        Some(direction) => Some(direction.next()).filter(|next| *next != rotation.from),
+        // start from the direction the preview is already showing
+        None => {
+            rotation.from = placement_target(transform, &physics, &blocks, &rotation)
+                .map(|(.., up)| up)
+                .unwrap_or_default();
+            Some(rotation.from.next())
+        }
+    };
+}
+
+fn placement_preview(
+    player: Query<&Inventory, With<Player>>,
+    transform: Query<&GlobalTransform, With<VoxelPlayer>>,
+    physics: Res<RapierContext>,
+    blocks: Query<&VoxelId>,
+    voxels: Res<Blocks>,
+    voxel_data: Res<Assets<Block>>,
+    rotation: Res<PlacementRotation>,
+    mut preview: ResMut<GhostPreview>,
+) {
+    let (Ok(inventory), Ok(transform)) = (player.get_single(), transform.get_single()) else {
+        return;
+    };
+    let ghost =
+        placement_target(transform, &physics, &blocks, &rotation).and_then(|(_, id, up)| {
+            let block_type = inventory.get_selected_block()?;
+            let block = voxel_data.get(voxels.get(block_type.clone()).id())?;
+            block.can_place().then(|| Ghost {
+                pos: id.0,
+                direction: if block_type.is_directional() {
+                    up
+                } else {
+                    MapDirection::default()
+                },
+                block: block_type,
+            })
+        });
+    preview.set_if_neq(GhostPreview(ghost.into_iter().collect()));
 }
 
 fn block_placing(
-    mut commands: Commands,
+    mut set_block: EventWriter<SetBlock>,
+    mut use_block: EventWriter<UseBlock>,
+    selected: Res<HexSelect>,
+    chunks: Res<Assets<VoxelChunk>>,
     mut player: Query<(&mut Inventory, &ActionState<PlayerAction>), With<Player>>,
     transform: Query<&GlobalTransform, With<VoxelPlayer>>,
     physics: Res<RapierContext>,
     blocks: Query<&VoxelId>,
-    selected: Res<HexSelect>,
-    mut chunk_data: ResMut<Assets<VoxelChunk>>,
     voxels: Res<Blocks>,
     voxel_data: Res<Assets<Block>>,
+    rotation: Res<PlacementRotation>,
 ) {
     let Ok((inventory, input)) = player.get_single() else {
         warn!("No Player Spawned");
@@ -181,54 +429,37 @@ fn block_placing(
         return;
     }
 
-    let Some((hit, normal)) = physics.cast_ray_and_get_normal(
-        transform.single().translation(),
-        transform.single().forward().as_vec3(),
-        6.,
-        false,
-        QueryFilter::only_fixed(),
-    ) else {
+    let Some((hit, id, up)) = placement_target(transform.single(), &physics, &blocks, &rotation)
+    else {
         return;
     };
-    let id = vec3_to_voxel_id(normal.normal);
-    let Ok(old) = blocks.get(hit) else {
-        panic!("hit block is not voxel");
-    };
-    let id = id + *old;
-    if !id.in_chunk() {
+    // switches are flipped rather than built against
+    if chunks
+        .get(selected.chunk.id())
+        .is_some_and(|chunk| is_switch(&chunk.get(hit)))
+    {
+        use_block.send(UseBlock(hit));
         return;
     }
-    let Some(chunk) = chunk_data.get_mut(selected.chunk.id()) else {
+    let Some(block_type) = inventory.get_selected_block() else {
         return;
     };
-    let Some(mut block_type) = inventory.get_selected_block() else {
-        return;
-    };
-    let up = normal_to_direction(normal.normal);
-    block_type.set_direction(up);
     let block = voxels.get(block_type.clone());
     let block = voxel_data.get(block.id()).expect("All blocks loaded");
-    let mut entity = commands.spawn((
-        Name::new("Test Block"),
-        id,
-        StateScoped(Screen::VoxelWorld),
-        PbrBundle {
-            mesh: block.mesh(),
-            material: block.material(),
-            transform: Transform::from_translation(id.0.as_vec3()).with_rotation(up.to_rotation()),
-            ..Default::default()
-        },
-    ));
-    block.add_components(&mut entity);
-    if block.is_solid() {
-        entity.insert(bevy_rapier3d::prelude::Collider::cuboid(0.5, 0.5, 0.5));
+    if !block.can_place() {
+        return;
     }
-    chunk.set(id.0, block_type.clone());
+    set_block.send(SetBlock {
+        pos: id.0,
+        block: block_type.clone(),
+        orientation: up,
+        cause: ChangeCause::Placed,
+    });
     let (mut inventory, _) = player.single_mut();
     inventory.check_and_deduct_resources(&[(block_type, 1)]);
 }
 
-fn vec3_to_voxel_id(vec: Vec3) -> VoxelId {
+pub(super) fn vec3_to_voxel_id(vec: Vec3) -> VoxelId {
     let abs = vec.abs();
 
     // x is biggest
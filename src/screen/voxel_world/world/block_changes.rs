@@ -0,0 +1,170 @@
+//! Every edit to the open chunk goes through [`SetBlock`] so the chunk data,
+//! voxel entities and anything listening for [`BlockChanged`] stay in sync.
+
+use bevy::prelude::*;
+
+use crate::{
+    game::HexSelect,
+    screen::{
+        hex_vox_util::MapDirection,
+        quests::QuestEvent,
+        voxel_world::voxels::{Block, BlockType, Blocks},
+        Screen,
+    },
+};
+
+use super::{
+    multi_block::{forget_replaced_machines, mark_multi_blocks},
+    spawn_voxel,
+    voxel_index::VoxelIndex,
+    ChunkRoot, VoxelChunk, VoxelId,
+};
+
+pub(crate) fn block_changes_plugin(app: &mut App) {
+    app.add_event::<SetBlock>()
+        .add_event::<BlockChanged>()
+        .add_systems(
+            PostUpdate,
+            (
+                apply_block_changes,
+                (
+                    block_change_audio,
+                    block_change_quests,
+                    mark_multi_blocks,
+                    forget_replaced_machines,
+                ),
+            )
+                .chain()
+                .run_if(in_state(Screen::VoxelWorld)),
+        );
+}
+
+/// Why a block was changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeCause {
+    /// The player placed it
+    Placed,
+    /// The player broke it
+    Broken,
+    /// A multi block was built
+    MultiBlock,
+    /// A blueprint was pasted
+    Blueprint,
+    /// Fluids met and one cooled into a block
+    Fluid,
+    /// A block lost its support or landed after falling
+    Fell,
+}
+
+/// Send this event to change a voxel in the open chunk
+#[derive(Event, Debug, Clone)]
+pub struct SetBlock {
+    pub pos: IVec3,
+    pub block: BlockType,
+    /// ignored unless the block is [`BlockType::is_directional`]
+    pub orientation: MapDirection,
+    pub cause: ChangeCause,
+}
+
+/// Sent once a [`SetBlock`] has been applied to the chunk
+#[derive(Event, Debug, Clone)]
+pub struct BlockChanged {
+    pub pos: IVec3,
+    pub old: BlockType,
+    pub new: BlockType,
+    pub cause: ChangeCause,
+}
+
+pub(super) fn apply_block_changes(
+    mut commands: Commands,
+    mut set_block: EventReader<SetBlock>,
+    mut changed: EventWriter<BlockChanged>,
+    selected: Res<HexSelect>,
+    mut chunks: ResMut<Assets<VoxelChunk>>,
+    root: Query<Entity, With<ChunkRoot>>,
+    index: Res<VoxelIndex>,
+    blocks: Res<Blocks>,
+    block_data: Res<Assets<Block>>,
+) {
+    if set_block.is_empty() {
+        return;
+    }
+    let Some(chunk) = chunks.get_mut(selected.chunk.id()) else {
+        set_block.clear();
+        return;
+    };
+    let Ok(root) = root.get_single() else {
+        warn!("No chunk root to spawn voxels on");
+        set_block.clear();
+        return;
+    };
+    // a position can change more than once a frame, only the last block gets an entity
+    let mut touched = Vec::new();
+    for event in set_block.read() {
+        if !VoxelId(event.pos).in_chunk() {
+            continue;
+        }
+        let old = chunk.set_oriented(event.pos, event.block.clone(), event.orientation);
+        if !touched.contains(&event.pos) {
+            touched.push(event.pos);
+        }
+        changed.send(BlockChanged {
+            pos: event.pos,
+            old,
+            new: event.block.clone(),
+            cause: event.cause,
+        });
+    }
+    for pos in touched.iter() {
+        if let Some(entity) = index.get(*pos) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    commands.entity(root).with_children(|voxels| {
+        for pos in touched {
+            spawn_voxel(
+                chunk.get(pos),
+                chunk.orientation(pos),
+                &blocks,
+                pos,
+                voxels,
+                &block_data,
+            );
+        }
+    });
+}
+
+fn block_change_audio(
+    mut commands: Commands,
+    mut changed: EventReader<BlockChanged>,
+    blocks: Res<Blocks>,
+    block_data: Res<Assets<Block>>,
+) {
+    let sounds = |block: &BlockType| {
+        block_data
+            .get(blocks.get(block.clone()).id())
+            .map(|data| data.sounds.clone())
+            .unwrap_or_default()
+    };
+    for change in changed.read() {
+        let sfx = match change.cause {
+            ChangeCause::Placed => sounds(&change.new).placed(),
+            ChangeCause::Broken => sounds(&change.old).broken(),
+            // landing after a fall sounds like being placed
+            ChangeCause::Fell if change.new != BlockType::Air => sounds(&change.new).placed(),
+            ChangeCause::MultiBlock
+            | ChangeCause::Blueprint
+            | ChangeCause::Fluid
+            | ChangeCause::Fell => continue,
+        };
+        commands.trigger(sfx.at(change.pos.as_vec3()));
+    }
+}
+
+fn block_change_quests(mut commands: Commands, mut changed: EventReader<BlockChanged>) {
+    for change in changed.read() {
+        if change.cause == ChangeCause::Broken && change.old != BlockType::Air {
+            commands.trigger(QuestEvent::Mined(change.old.clone()));
+        }
+    }
+}
@@ -0,0 +1,398 @@
+//! Copy a box of voxels into a named blueprint and paste it somewhere else.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::{
+    game::{assets::SfxKey, audio::sfx::PlaySfx, main_character::Player, HexSelect, PlayerAction},
+    screen::{
+        hex_vox_util::MapDirection,
+        inventory::Inventory,
+        voxel_world::{voxel_util::VoxelPlayer, voxels::BlockType},
+        Screen,
+    },
+};
+
+use super::{
+    block_breaking::vec3_to_voxel_id,
+    block_changes::{ChangeCause, SetBlock},
+    ghost::{Ghost, GhostPreview},
+    VoxelChunk, VoxelId,
+};
+
+pub(crate) fn blueprint_plugin(app: &mut App) {
+    app.init_resource::<Blueprints>()
+        .init_resource::<BlueprintTool>()
+        .add_systems(
+            Update,
+            (
+                blueprint_controls,
+                select_region,
+                paste_blueprint,
+                draw_selection,
+                update_blueprint_hint,
+            )
+                .chain()
+                .run_if(in_state(Screen::VoxelWorld)),
+        )
+        .add_systems(OnEnter(Screen::VoxelWorld), spawn_blueprint_hint)
+        .add_systems(OnExit(Screen::VoxelWorld), reset_tool);
+}
+
+/// A copy of a box of voxels, stored the same way as a [`VoxelChunk`]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Blueprint {
+    pub name: String,
+    pub size: IVec3,
+    blocks: Vec<BlockType>,
+    orientations: Vec<MapDirection>,
+}
+
+impl Blueprint {
+    /// copies every voxel between `a` and `b` inclusive
+    pub fn from_region(name: String, chunk: &VoxelChunk, a: IVec3, b: IVec3) -> Blueprint {
+        let min = a.min(b);
+        let size = a.max(b) - min + IVec3::ONE;
+        let mut blocks = Vec::with_capacity((size.x * size.y * size.z) as usize);
+        let mut orientations = Vec::with_capacity(blocks.capacity());
+        for y in 0..size.y {
+            for z in 0..size.z {
+                for x in 0..size.x {
+                    blocks.push(chunk.get(min + IVec3::new(x, y, z)));
+                    orientations.push(chunk.orientation(min + IVec3::new(x, y, z)));
+                }
+            }
+        }
+        Blueprint {
+            name,
+            size,
+            blocks,
+            orientations,
+        }
+    }
+
+    fn index(&self, offset: IVec3) -> usize {
+        (offset.x + offset.z * self.size.x + offset.y * self.size.x * self.size.z) as usize
+    }
+
+    pub fn get(&self, offset: IVec3) -> BlockType {
+        self.blocks[self.index(offset)].clone()
+    }
+
+    pub fn orientation(&self, offset: IVec3) -> MapDirection {
+        self.orientations[self.index(offset)]
+    }
+
+    /// every block needed to build this blueprint
+    pub fn requirements(&self) -> Vec<(BlockType, u32)> {
+        let mut out: Vec<(BlockType, u32)> = Vec::new();
+        for block in self.blocks.iter().filter(|block| **block != BlockType::Air) {
+            if let Some((_, count)) = out.iter_mut().find(|(other, _)| other == block) {
+                *count += 1;
+            } else {
+                out.push((block.clone(), 1));
+            }
+        }
+        out
+    }
+
+    /// where each block goes when pasted at `origin` with its up turned to face `direction`
+    pub fn placements(
+        &self,
+        origin: IVec3,
+        direction: MapDirection,
+    ) -> Vec<(IVec3, BlockType, MapDirection)> {
+        let rotation = direction.to_rotation();
+        let mut out = Vec::new();
+        for y in 0..self.size.y {
+            for z in 0..self.size.z {
+                for x in 0..self.size.x {
+                    let offset = IVec3::new(x, y, z);
+                    let block = self.get(offset);
+                    if block == BlockType::Air {
+                        continue;
+                    }
+                    let orientation = rotate_direction(self.orientation(offset), rotation);
+                    let offset = (rotation * offset.as_vec3()).round().as_ivec3();
+                    out.push((origin + offset, block, orientation));
+                }
+            }
+        }
+        out
+    }
+}
+
+fn facing(direction: MapDirection) -> Vec3 {
+    direction.to_rotation() * Vec3::Y
+}
+
+fn rotate_direction(direction: MapDirection, rotation: Quat) -> MapDirection {
+    let target = rotation * facing(direction);
+    MapDirection::iter()
+        .max_by(|a, b| facing(*a).dot(target).total_cmp(&facing(*b).dot(target)))
+        .unwrap_or_default()
+}
+
+/// Resource of every blueprint the player has saved
+#[derive(Resource, Default, Serialize, Deserialize, Debug)]
+pub struct Blueprints {
+    pub saved: Vec<Blueprint>,
+    pub selected: usize,
+}
+
+impl Blueprints {
+    pub fn current(&self) -> Option<&Blueprint> {
+        self.saved.get(self.selected)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlueprintMode {
+    #[default]
+    Off,
+    /// picking the corners of the box to copy
+    Select(Option<IVec3>),
+    Paste,
+}
+
+/// Resource for what the blueprint tool is doing
+#[derive(Resource, Default)]
+pub struct BlueprintTool {
+    pub mode: BlueprintMode,
+    pub direction: MapDirection,
+}
+
+/// Run condition for systems that should stop while the blueprint tool uses the place button
+pub fn blueprint_inactive(tool: Res<BlueprintTool>) -> bool {
+    tool.mode == BlueprintMode::Off
+}
+
+fn reset_tool(mut tool: ResMut<BlueprintTool>, mut preview: ResMut<GhostPreview>) {
+    tool.mode = BlueprintMode::Off;
+    preview.0.clear();
+}
+
+fn blueprint_controls(
+    input: Query<&ActionState<PlayerAction>, With<Player>>,
+    mut tool: ResMut<BlueprintTool>,
+    mut blueprints: ResMut<Blueprints>,
+) {
+    let Ok(input) = input.get_single() else {
+        return;
+    };
+    if input.just_pressed(&PlayerAction::Blueprint) {
+        tool.mode = match tool.mode {
+            BlueprintMode::Off => BlueprintMode::Select(None),
+            BlueprintMode::Select(_) if !blueprints.saved.is_empty() => BlueprintMode::Paste,
+            _ => BlueprintMode::Off,
+        };
+    }
+    if input.just_pressed(&PlayerAction::BlueprintNext) && !blueprints.saved.is_empty() {
+        blueprints.selected = (blueprints.selected + 1) % blueprints.saved.len();
+    }
+    if input.just_pressed(&PlayerAction::Rotate) && tool.mode == BlueprintMode::Paste {
+        tool.direction = tool.direction.next();
+    }
+}
+
+/// The voxel the player is looking at and the face normal that was hit
+pub(super) fn target_voxel(
+    physics: &RapierContext,
+    player: &GlobalTransform,
+    voxels: &Query<&VoxelId>,
+) -> Option<(IVec3, Vec3)> {
+    let (hit, normal) = physics.cast_ray_and_get_normal(
+        player.translation(),
+        player.forward().as_vec3(),
+        6.,
+        false,
+        QueryFilter::only_fixed(),
+    )?;
+    voxels.get(hit).ok().map(|id| (id.0, normal.normal))
+}
+
+fn select_region(
+    mut commands: Commands,
+    input: Query<&ActionState<PlayerAction>, With<Player>>,
+    player: Query<&GlobalTransform, With<VoxelPlayer>>,
+    physics: Res<RapierContext>,
+    voxels: Query<&VoxelId>,
+    selected: Res<HexSelect>,
+    chunks: Res<Assets<VoxelChunk>>,
+    mut tool: ResMut<BlueprintTool>,
+    mut blueprints: ResMut<Blueprints>,
+) {
+    let BlueprintMode::Select(first) = tool.mode else {
+        return;
+    };
+    let (Ok(input), Ok(player)) = (input.get_single(), player.get_single()) else {
+        return;
+    };
+    if !input.just_pressed(&PlayerAction::Place) {
+        return;
+    }
+    let Some((pos, _)) = target_voxel(&physics, player, &voxels) else {
+        return;
+    };
+    if !VoxelId(pos).in_chunk() {
+        return;
+    }
+    let Some(first) = first else {
+        tool.mode = BlueprintMode::Select(Some(pos));
+        commands.trigger(PlaySfx::RandomStep);
+        return;
+    };
+    let Some(chunk) = chunks.get(selected.chunk.id()) else {
+        return;
+    };
+    let name = format!("Blueprint {}", blueprints.saved.len() + 1);
+    info!("Saved {name}");
+    blueprints
+        .saved
+        .push(Blueprint::from_region(name, chunk, first, pos));
+    blueprints.selected = blueprints.saved.len() - 1;
+    tool.mode = BlueprintMode::Paste;
+    commands.trigger(PlaySfx::Key(SfxKey::Progress));
+}
+
+fn paste_blueprint(
+    mut commands: Commands,
+    mut input: Query<(&ActionState<PlayerAction>, &mut Inventory), With<Player>>,
+    player: Query<&GlobalTransform, With<VoxelPlayer>>,
+    physics: Res<RapierContext>,
+    voxels: Query<&VoxelId>,
+    selected: Res<HexSelect>,
+    chunks: Res<Assets<VoxelChunk>>,
+    tool: Res<BlueprintTool>,
+    blueprints: Res<Blueprints>,
+    mut preview: ResMut<GhostPreview>,
+    mut set_block: EventWriter<SetBlock>,
+) {
+    // single block placing owns the preview while the tool is off
+    if tool.mode == BlueprintMode::Off {
+        return;
+    }
+    let (Ok((input, mut inventory)), Ok(player)) = (input.get_single_mut(), player.get_single())
+    else {
+        return;
+    };
+    let target = target_voxel(&physics, player, &voxels)
+        .map(|(pos, normal)| pos + vec3_to_voxel_id(normal).0);
+    let (Some(blueprint), Some(origin), BlueprintMode::Paste) =
+        (blueprints.current(), target, tool.mode)
+    else {
+        preview.set_if_neq(GhostPreview::default());
+        return;
+    };
+    let placements = blueprint.placements(origin, tool.direction);
+    preview.set_if_neq(GhostPreview(
+        placements
+            .iter()
+            .map(|(pos, block, orientation)| Ghost {
+                pos: *pos,
+                block: block.clone(),
+                direction: *orientation,
+            })
+            .collect(),
+    ));
+
+    // the press that finished a selection should not also paste it
+    if !input.just_pressed(&PlayerAction::Place) || tool.is_changed() {
+        return;
+    }
+    let Some(chunk) = chunks.get(selected.chunk.id()) else {
+        return;
+    };
+    let fits = placements
+        .iter()
+        .all(|(pos, ..)| VoxelId(*pos).in_chunk() && chunk.get(*pos) == BlockType::Air);
+    if !fits || !inventory.check_and_deduct_resources(&blueprint.requirements()) {
+        commands.trigger(PlaySfx::Key(SfxKey::NoProgress));
+        return;
+    }
+    for (pos, block, orientation) in placements {
+        set_block.send(SetBlock {
+            pos,
+            block,
+            orientation,
+            cause: ChangeCause::Blueprint,
+        });
+    }
+    commands.trigger(PlaySfx::Key(SfxKey::Progress));
+}
+
+/// outlines the box being selected
+fn draw_selection(
+    mut gizmos: Gizmos,
+    tool: Res<BlueprintTool>,
+    player: Query<&GlobalTransform, With<VoxelPlayer>>,
+    physics: Res<RapierContext>,
+    voxels: Query<&VoxelId>,
+) {
+    let BlueprintMode::Select(first) = tool.mode else {
+        return;
+    };
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let Some((target, _)) = target_voxel(&physics, player, &voxels) else {
+        return;
+    };
+    let first = first.unwrap_or(target);
+    let min = first.min(target).as_vec3() - Vec3::splat(0.5);
+    let max = first.max(target).as_vec3() + Vec3::splat(0.5);
+    gizmos.cuboid(
+        Transform::from_translation((min + max) / 2.).with_scale(max - min),
+        LinearRgba::GREEN,
+    );
+}
+
+#[derive(Component)]
+struct BlueprintHint;
+
+fn spawn_blueprint_hint(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Blueprint Hint"),
+        BlueprintHint,
+        StateScoped(Screen::VoxelWorld),
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: crate::ui::palette::LABEL_TEXT,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.),
+            left: Val::Px(10.),
+            ..Default::default()
+        }),
+    ));
+}
+
+fn update_blueprint_hint(
+    tool: Res<BlueprintTool>,
+    blueprints: Res<Blueprints>,
+    mut hint: Query<&mut Text, With<BlueprintHint>>,
+) {
+    if !tool.is_changed() && !blueprints.is_changed() {
+        return;
+    }
+    let value = match tool.mode {
+        BlueprintMode::Off => String::new(),
+        BlueprintMode::Select(None) => "Blueprint: place on the first corner".to_string(),
+        BlueprintMode::Select(Some(_)) => "Blueprint: place on the second corner".to_string(),
+        BlueprintMode::Paste => match blueprints.current() {
+            Some(blueprint) => format!("Blueprint: {} facing {:?}", blueprint.name, tool.direction),
+            None => String::new(),
+        },
+    };
+    for mut text in &mut hint {
+        text.sections[0].value = value.clone();
+    }
+}
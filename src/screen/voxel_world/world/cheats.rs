@@ -2,28 +2,57 @@ use bevy::prelude::*;
 
 use crate::{
     game::main_character::Player,
-    screen::{inventory::Inventory, voxel_world::voxels::BlockType},
+    screen::{
+        inventory::Inventory,
+        voxel_world::{
+            item::DropItems,
+            voxels::{Block, BlockType, Blocks},
+        },
+    },
 };
 
 pub fn give_player_block(
     mut player: Query<&mut Inventory, With<Player>>,
     input: Res<ButtonInput<KeyCode>>,
+    blocks: Res<Blocks>,
+    block_data: Res<Assets<Block>>,
+    mut spilled: EventWriter<DropItems>,
 ) {
     for key in input.get_just_pressed() {
         let give = match key {
             KeyCode::Numpad0 => BlockType::Stone,
             KeyCode::Numpad1 => BlockType::Coal,
             KeyCode::Numpad2 => BlockType::IronOre,
-            KeyCode::Numpad3 => BlockType::Drill(crate::screen::hex_vox_util::MapDirection::Up),
+            KeyCode::Numpad3 => BlockType::Drill,
             KeyCode::Numpad4 => BlockType::Score,
-            KeyCode::Numpad5 => BlockType::Piston(crate::screen::hex_vox_util::MapDirection::Up),
-            KeyCode::Numpad6 => BlockType::PistonL2(crate::screen::hex_vox_util::MapDirection::Up),
-            KeyCode::Numpad7 => BlockType::Conveyor(crate::screen::hex_vox_util::MapDirection::Up),
+            KeyCode::Numpad5 => BlockType::Piston,
+            KeyCode::Numpad6 => BlockType::PistonL2,
+            KeyCode::Numpad7 => BlockType::Conveyor,
+            KeyCode::Numpad8 => BlockType::Lever,
+            KeyCode::Numpad9 => BlockType::Wire,
+            KeyCode::NumpadAdd => BlockType::Button,
+            KeyCode::NumpadDecimal => BlockType::PressurePlate,
+            KeyCode::NumpadEnter => BlockType::ItemDetector,
+            KeyCode::NumpadDivide => BlockType::NotGate,
+            KeyCode::NumpadMultiply => BlockType::AndGate,
+            KeyCode::F5 => BlockType::Generator,
+            KeyCode::F6 => BlockType::Battery,
+            KeyCode::F7 => BlockType::Cable,
+            KeyCode::F8 => BlockType::Pipe,
+            KeyCode::F9 => BlockType::Tank,
+            KeyCode::F10 => BlockType::CastingBasin,
             _ => continue,
         };
         for mut inventory in &mut player {
             warn!("cheated {:?} in", give);
-            inventory.add_resource(give.clone(), 200);
+            let max_stack = blocks.max_stack(&block_data, &give);
+            let overflow = inventory.add_resource(give.clone(), 200, max_stack);
+            if overflow > 0 {
+                spilled.send(DropItems {
+                    block_type: give.clone(),
+                    count: overflow,
+                });
+            }
         }
     }
 }
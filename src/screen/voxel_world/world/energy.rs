@@ -0,0 +1,429 @@
+//! Energy made by generators burning fuel, carried by cables and spent by machines.
+//!
+//! Generators, batteries and cables that touch form a network. Every fixed tick the networks are
+//! rebuilt from the chunk, machines next to a network draw from it as they work and whatever is
+//! left is stored back into the generators and batteries. Machines next to no network at all
+//! run without energy.
+
+use std::collections::VecDeque;
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_rapier3d::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    game::{main_character::Player, HexSelect, PlayerAction},
+    screen::{
+        voxel_world::{
+            item::Item,
+            ui::{spawn_hud_column, HudColumn},
+            voxel_util::VoxelPlayer,
+            voxels::{Block, BlockType, Blocks},
+        },
+        Screen,
+    },
+};
+
+use super::{blueprint::target_voxel, VoxelChunk, VoxelId, CHUNK_SIZE, NEIGHBOURS};
+
+/// Energy from burning one fuel item
+pub const FUEL_ENERGY: u32 = 50;
+/// Energy a drill uses for every item it mines
+pub const DRILL_COST: u32 = 5;
+/// Energy a piston uses for every push
+pub const PISTON_COST: u32 = 2;
+/// Energy a conveyor uses for every second it runs
+pub const CONVEYOR_COST: u32 = 2;
+
+pub(crate) fn energy_plugin(app: &mut App) {
+    app.init_resource::<EnergyNetworks>()
+        .add_systems(
+            FixedUpdate,
+            (
+                (build_networks, burn_fuel)
+                    .chain()
+                    .in_set(EnergySet::Supply),
+                (store_energy, record_flow).in_set(EnergySet::Store),
+            )
+                .run_if(in_state(Screen::VoxelWorld)),
+        )
+        .configure_sets(FixedUpdate, EnergySet::Supply.before(EnergySet::Store))
+        .add_systems(OnExit(Screen::VoxelWorld), clear_networks);
+}
+
+/// The overlay showing the network the player is looking at, kept apart so the logic runs headless
+pub(crate) fn energy_overlay_plugin(app: &mut App) {
+    app.init_resource::<EnergyOverlay>()
+        .add_systems(
+            Update,
+            (toggle_overlay, draw_overlay, update_overlay_text)
+                .chain()
+                .run_if(in_state(Screen::VoxelWorld)),
+        )
+        .add_systems(
+            OnEnter(Screen::VoxelWorld),
+            spawn_overlay_text.after(spawn_hud_column),
+        );
+}
+
+/// Machines that use energy run between [`EnergySet::Supply`] and [`EnergySet::Store`]
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnergySet {
+    Supply,
+    Store,
+}
+
+/// How much energy a block can hold, blocks that hold none do not store energy
+pub fn capacity(block: &BlockType) -> u32 {
+    match block {
+        BlockType::Generator => 100,
+        BlockType::Battery => 1000,
+        _ => 0,
+    }
+}
+
+/// Blocks that join their neighbours into one network
+fn connects(block: &BlockType) -> bool {
+    matches!(
+        block,
+        BlockType::Generator | BlockType::Battery | BlockType::Cable
+    )
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct EnergyNetwork {
+    pub members: Vec<IVec3>,
+    pub stored: u32,
+    pub capacity: u32,
+    /// energy added by generators this tick
+    pub produced: u32,
+    /// energy drawn by machines this tick
+    pub consumed: u32,
+}
+
+/// What a network added and drew on one fixed tick
+#[derive(Debug, Clone, Copy)]
+struct Flow {
+    at: f32,
+    produced: u32,
+    consumed: u32,
+}
+
+/// Resource of every network in the open chunk, rebuilt each fixed tick
+#[derive(Resource, Default)]
+pub struct EnergyNetworks {
+    networks: Vec<EnergyNetwork>,
+    /// the network each member belongs to
+    member_of: HashMap<IVec3, usize>,
+    /// the flow of each network over the last second, kept across rebuilds by its first member
+    history: HashMap<IVec3, VecDeque<Flow>>,
+}
+
+impl EnergyNetworks {
+    pub fn get(&self, pos: IVec3) -> Option<&EnergyNetwork> {
+        self.member_of.get(&pos).map(|index| &self.networks[*index])
+    }
+
+    /// Energy added by generators and drawn by machines in `network` over the last second
+    pub fn per_second(&self, network: &EnergyNetwork) -> (u32, u32) {
+        self.history
+            .get(&network.members[0])
+            .into_iter()
+            .flatten()
+            .fold((0, 0), |(produced, consumed), flow| {
+                (produced + flow.produced, consumed + flow.consumed)
+            })
+    }
+
+    /// Energy drawn by machines in every network this tick
+    pub fn consumed(&self) -> u32 {
+        self.networks.iter().map(|network| network.consumed).sum()
+    }
+
+    /// Takes `amount` from a network touching `pos`, false if none of them have enough and
+    /// true without taking anything when `pos` touches no network
+    pub fn draw(&mut self, pos: IVec3, amount: u32) -> bool {
+        let touching = NEIGHBOURS
+            .iter()
+            .filter_map(|offset| self.member_of.get(&(pos + *offset)).copied())
+            .collect::<HashSet<_>>();
+        if touching.is_empty() {
+            return true;
+        }
+        for index in touching {
+            let network = &mut self.networks[index];
+            if network.stored >= amount {
+                network.stored -= amount;
+                network.consumed += amount;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+fn clear_networks(mut networks: ResMut<EnergyNetworks>) {
+    *networks = EnergyNetworks::default();
+}
+
+fn build_networks(
+    selected: Res<HexSelect>,
+    chunks: Res<Assets<VoxelChunk>>,
+    mut networks: ResMut<EnergyNetworks>,
+) {
+    *networks = EnergyNetworks {
+        history: std::mem::take(&mut networks.history),
+        ..default()
+    };
+    let Some(chunk) = chunks.get(selected.chunk.id()) else {
+        return;
+    };
+    let size = CHUNK_SIZE as i32;
+    for x in 0..size {
+        for y in 0..size {
+            for z in 0..size {
+                let start = IVec3::new(x, y, z);
+                if networks.member_of.contains_key(&start) || !connects(&chunk.get(start)) {
+                    continue;
+                }
+                let index = networks.networks.len();
+                let mut network = EnergyNetwork::default();
+                let mut open = vec![start];
+                networks.member_of.insert(start, index);
+                while let Some(pos) = open.pop() {
+                    let block = chunk.get(pos);
+                    network.capacity += capacity(&block);
+                    network.stored += chunk.energy(pos);
+                    network.members.push(pos);
+                    for offset in NEIGHBOURS {
+                        let next = pos + offset;
+                        if VoxelId(next).in_chunk()
+                            && !networks.member_of.contains_key(&next)
+                            && connects(&chunk.get(next))
+                        {
+                            networks.member_of.insert(next, index);
+                            open.push(next);
+                        }
+                    }
+                }
+                networks.networks.push(network);
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Generator;
+
+/// Generators burn fuel items resting on top of them while their network has room
+fn burn_fuel(
+    mut commands: Commands,
+    generators: Query<(&VoxelId, &Transform), With<Generator>>,
+    context: Res<RapierContext>,
+    items: Query<&BlockType, With<Item>>,
+    voxels: Res<Blocks>,
+    data: Res<Assets<Block>>,
+    mut networks: ResMut<EnergyNetworks>,
+) {
+    for (id, pos) in &generators {
+        let Some(&index) = networks.member_of.get(&id.0) else {
+            continue;
+        };
+        let network = &mut networks.networks[index];
+        if network.stored + FUEL_ENERGY > network.capacity {
+            continue;
+        }
+        let Some((above, _)) = context.cast_shape(
+            pos.translation,
+            Quat::IDENTITY,
+            Vec3::Y,
+            &Collider::cuboid(0.5, 0.5, 0.5),
+            ShapeCastOptions {
+                max_time_of_impact: 1.,
+                target_distance: 0.5,
+                stop_at_penetration: false,
+                compute_impact_geometry_on_penetration: false,
+            },
+            QueryFilter::only_dynamic(),
+        ) else {
+            continue;
+        };
+        let Ok(item) = items.get(above) else {
+            continue;
+        };
+        let fuel = voxels.get(item.clone());
+        if !data.get(fuel.id()).is_some_and(Block::is_fuel) {
+            continue;
+        }
+        commands.entity(above).despawn_recursive();
+        network.stored += FUEL_ENERGY;
+        network.produced += FUEL_ENERGY;
+    }
+}
+
+/// Fills the generators and batteries of each network with what it has left
+fn store_energy(
+    selected: Res<HexSelect>,
+    mut chunks: ResMut<Assets<VoxelChunk>>,
+    networks: Res<EnergyNetworks>,
+) {
+    if networks.networks.is_empty() {
+        return;
+    }
+    let Some(chunk) = chunks.get(selected.chunk.id()) else {
+        return;
+    };
+    let mut changed = Vec::new();
+    for network in networks.networks.iter() {
+        let mut left = network.stored;
+        for pos in network.members.iter() {
+            let amount = left.min(capacity(&chunk.get(*pos)));
+            if chunk.energy(*pos) != amount {
+                changed.push((*pos, amount));
+            }
+            left -= amount;
+        }
+    }
+    // only touch the chunk when something changed, so it is not marked modified every tick
+    if changed.is_empty() {
+        return;
+    }
+    let Some(chunk) = chunks.get_mut(selected.chunk.id()) else {
+        return;
+    };
+    for (pos, amount) in changed {
+        chunk.set_energy(pos, amount);
+    }
+}
+
+/// Adds this tick to the flow of each network, forgetting networks that are gone
+fn record_flow(time: Res<Time>, mut networks: ResMut<EnergyNetworks>) {
+    let now = time.elapsed_seconds();
+    let mut history = std::mem::take(&mut networks.history);
+    networks.history = networks
+        .networks
+        .iter()
+        .map(|network| {
+            let mut flows = history.remove(&network.members[0]).unwrap_or_default();
+            flows.push_back(Flow {
+                at: now,
+                produced: network.produced,
+                consumed: network.consumed,
+            });
+            while flows.front().is_some_and(|flow| now - flow.at >= 1.) {
+                flows.pop_front();
+            }
+            (network.members[0], flows)
+        })
+        .collect();
+}
+
+/// Resource for whether the energy overlay is shown
+#[derive(Resource, Default)]
+pub struct EnergyOverlay(pub bool);
+
+fn toggle_overlay(
+    input: Query<&ActionState<PlayerAction>, With<Player>>,
+    mut overlay: ResMut<EnergyOverlay>,
+) {
+    let Ok(input) = input.get_single() else {
+        return;
+    };
+    if input.just_pressed(&PlayerAction::EnergyOverlay) {
+        overlay.0 = !overlay.0;
+    }
+}
+
+/// The network the player is looking at, or one touching the machine they are looking at
+fn inspected<'a>(
+    networks: &'a EnergyNetworks,
+    physics: &RapierContext,
+    player: &Query<&GlobalTransform, With<VoxelPlayer>>,
+    voxels: &Query<&VoxelId>,
+) -> Option<&'a EnergyNetwork> {
+    let player = player.get_single().ok()?;
+    let (pos, _) = target_voxel(physics, player, voxels)?;
+    networks.get(pos).or_else(|| {
+        NEIGHBOURS
+            .iter()
+            .find_map(|offset| networks.get(pos + *offset))
+    })
+}
+
+fn draw_overlay(
+    mut gizmos: Gizmos,
+    overlay: Res<EnergyOverlay>,
+    networks: Res<EnergyNetworks>,
+    physics: Res<RapierContext>,
+    player: Query<&GlobalTransform, With<VoxelPlayer>>,
+    voxels: Query<&VoxelId>,
+) {
+    if !overlay.0 {
+        return;
+    }
+    let Some(network) = inspected(&networks, &physics, &player, &voxels) else {
+        return;
+    };
+    for pos in network.members.iter() {
+        gizmos.cuboid(
+            Transform::from_translation(pos.as_vec3()).with_scale(Vec3::splat(1.05)),
+            LinearRgba::rgb(1., 0.8, 0.),
+        );
+    }
+}
+
+#[derive(Component)]
+struct EnergyOverlayText;
+
+fn spawn_overlay_text(mut commands: Commands, column: Query<Entity, With<HudColumn>>) {
+    let Ok(column) = column.get_single() else {
+        return;
+    };
+    commands.entity(column).with_children(|column| {
+        column.spawn((
+            Name::new("Energy Overlay"),
+            EnergyOverlayText,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 20.0,
+                    color: crate::ui::palette::LABEL_TEXT,
+                    ..default()
+                },
+            ),
+        ));
+    });
+}
+
+fn update_overlay_text(
+    overlay: Res<EnergyOverlay>,
+    networks: Res<EnergyNetworks>,
+    physics: Res<RapierContext>,
+    player: Query<&GlobalTransform, With<VoxelPlayer>>,
+    voxels: Query<&VoxelId>,
+    mut text: Query<&mut Text, With<EnergyOverlayText>>,
+) {
+    let value = if !overlay.0 {
+        String::new()
+    } else if let Some(network) = inspected(&networks, &physics, &player, &voxels) {
+        let (produced, consumed) = networks.per_second(network);
+        format!(
+            "Energy {}/{}\n+{} -{} per second\n{} blocks",
+            network.stored,
+            network.capacity,
+            produced,
+            consumed,
+            network.members.len()
+        )
+    } else {
+        "Energy: look at a network".to_string()
+    };
+    for mut text in &mut text {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
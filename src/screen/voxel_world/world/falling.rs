@@ -0,0 +1,273 @@
+//! Blocks flagged with [`BlockFlags::Falls`](crate::screen::voxel_world::voxels::BlockFlags::Falls)
+//! drop as rapier bodies when nothing solid is under them and become voxels again where they land.
+//!
+//! Only positions next to a change are checked, on the fixed tick so columns collapse one block
+//! at a time and tests can step through them.
+
+use bevy::{prelude::*, utils::HashSet};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    game::{save::save_chunk_data, HexSelect},
+    screen::{
+        hex_vox_util::MapDirection,
+        voxel_world::{
+            item::spawn_item,
+            voxels::{Block, BlockType, Blocks},
+        },
+        Screen,
+    },
+};
+
+use super::{
+    block_changes::{apply_block_changes, BlockChanged, ChangeCause, SetBlock},
+    VoxelChunk, VoxelId, CHUNK_SIZE,
+};
+
+pub(crate) fn falling_plugin(app: &mut App) {
+    app.init_resource::<FallChecks>()
+        .add_systems(
+            FixedUpdate,
+            (land_falling_blocks, drop_unsupported_blocks)
+                .chain()
+                .run_if(in_state(Screen::VoxelWorld)),
+        )
+        .add_systems(
+            PostUpdate,
+            (queue_fall_checks, spawn_falling_blocks)
+                .after(apply_block_changes)
+                .run_if(in_state(Screen::VoxelWorld)),
+        )
+        .add_systems(OnEnter(Screen::VoxelWorld), check_whole_chunk)
+        .add_systems(
+            OnExit(Screen::VoxelWorld),
+            land_before_leaving.before(save_chunk_data),
+        );
+}
+
+/// Resource of positions that might have lost what was holding them up
+#[derive(Resource, Default)]
+pub struct FallChecks {
+    positions: HashSet<IVec3>,
+    /// set when a chunk is opened, cleared once it has been looked through
+    whole_chunk: bool,
+    /// blocks taken out of the chunk this tick, given bodies once their voxels are gone
+    dropped: Vec<(IVec3, FallingBlock)>,
+}
+
+/// A block on its way down, turned back into a voxel once it stops
+#[derive(Component)]
+pub struct FallingBlock {
+    pub block: BlockType,
+    pub orientation: MapDirection,
+    /// the height it started falling from
+    start: f32,
+    /// fixed ticks since it started falling
+    ticks: u32,
+}
+
+impl FallingBlock {
+    pub fn new(block: BlockType, orientation: MapDirection, from: IVec3) -> FallingBlock {
+        FallingBlock {
+            block,
+            orientation,
+            start: from.y as f32,
+            ticks: 0,
+        }
+    }
+}
+
+/// How far a body has to drop before stopping counts as landing. New bodies have not been
+/// moved by physics yet and would otherwise land where they started.
+const MIN_FALL: f32 = 0.5;
+
+/// Fixed ticks a body can sit without falling before it is given up on
+const STUCK_TICKS: u32 = 64;
+
+fn check_whole_chunk(mut checks: ResMut<FallChecks>) {
+    checks.whole_chunk = true;
+}
+
+fn queue_fall_checks(mut changed: EventReader<BlockChanged>, mut checks: ResMut<FallChecks>) {
+    for change in changed.read() {
+        checks.positions.insert(change.pos);
+        checks.positions.insert(change.pos + IVec3::Y);
+    }
+}
+
+fn is_solid(block: BlockType, blocks: &Blocks, data: &Assets<Block>) -> bool {
+    data.get(blocks.get(block).id())
+        .is_some_and(Block::is_solid)
+}
+
+fn drop_unsupported_blocks(
+    mut checks: ResMut<FallChecks>,
+    selected: Res<HexSelect>,
+    chunks: Res<Assets<VoxelChunk>>,
+    blocks: Res<Blocks>,
+    data: Res<Assets<Block>>,
+    mut set_block: EventWriter<SetBlock>,
+) {
+    let Some(chunk) = chunks.get(selected.chunk.id()) else {
+        return;
+    };
+    let mut positions = std::mem::take(&mut checks.positions)
+        .into_iter()
+        .collect::<Vec<_>>();
+    if std::mem::take(&mut checks.whole_chunk) {
+        let size = CHUNK_SIZE as i32;
+        for x in 0..size {
+            for y in 0..size {
+                for z in 0..size {
+                    positions.push(IVec3::new(x, y, z));
+                }
+            }
+        }
+    }
+    // the same order every tick so tests see the same result
+    positions.sort_by_key(|pos| (pos.y, pos.x, pos.z));
+    positions.dedup();
+
+    for pos in positions {
+        if !VoxelId(pos).in_chunk() {
+            continue;
+        }
+        let block = chunk.get(pos);
+        let Some(block_data) = data.get(blocks.get(block.clone()).id()) else {
+            continue;
+        };
+        if !block_data.falls() || is_solid(chunk.get(pos - IVec3::Y), &blocks, &data) {
+            continue;
+        }
+        set_block.send(SetBlock {
+            pos,
+            block: BlockType::Air,
+            orientation: MapDirection::default(),
+            cause: ChangeCause::Fell,
+        });
+        let orientation = chunk.orientation(pos);
+        checks
+            .dropped
+            .push((pos, FallingBlock::new(block, orientation, pos)));
+    }
+}
+
+/// Runs after the dropped voxels are despawned so the bodies never start inside them
+fn spawn_falling_blocks(
+    mut commands: Commands,
+    mut checks: ResMut<FallChecks>,
+    blocks: Res<Blocks>,
+    data: Res<Assets<Block>>,
+) {
+    for (pos, falling) in checks.dropped.drain(..) {
+        let Some(block_data) = data.get(blocks.get(falling.block.clone()).id()) else {
+            continue;
+        };
+        commands.spawn((
+            Name::new("Falling Block"),
+            PbrBundle {
+                mesh: block_data.mesh(),
+                material: block_data.material(),
+                transform: Transform::from_translation(pos.as_vec3())
+                    .with_rotation(falling.orientation.to_rotation()),
+                ..Default::default()
+            },
+            // not state scoped, `land_before_leaving` puts it back in the chunk
+            falling,
+            RigidBody::Dynamic,
+            // a little smaller than a voxel so it does not catch on its neighbours
+            Collider::cuboid(0.45, 0.45, 0.45),
+            LockedAxes::ROTATION_LOCKED
+                | LockedAxes::TRANSLATION_LOCKED_X
+                | LockedAxes::TRANSLATION_LOCKED_Z,
+            Velocity::default(),
+        ));
+    }
+}
+
+/// The first open voxel at or above `pos`, where a stopped block can go
+fn landing_spot(chunk: &VoxelChunk, mut pos: IVec3) -> Option<IVec3> {
+    while VoxelId(pos).in_chunk() {
+        if chunk.get(pos) == BlockType::Air {
+            return Some(pos);
+        }
+        pos += IVec3::Y;
+    }
+    None
+}
+
+fn land_falling_blocks(
+    mut commands: Commands,
+    mut falling: Query<(Entity, &mut FallingBlock, &Transform, &Velocity)>,
+    selected: Res<HexSelect>,
+    chunks: Res<Assets<VoxelChunk>>,
+    blocks: Res<Blocks>,
+    data: Res<Assets<Block>>,
+    mut set_block: EventWriter<SetBlock>,
+) {
+    let Some(chunk) = chunks.get(selected.chunk.id()) else {
+        return;
+    };
+    for (entity, mut falling, transform, velocity) in &mut falling {
+        falling.ticks += 1;
+        if velocity.linvel.y.abs() > 0.1 {
+            continue;
+        }
+        let landed = falling.start - transform.translation.y >= MIN_FALL;
+        if !landed && falling.ticks < STUCK_TICKS {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+        // held up by something that is not solid, so putting it back would only drop it again
+        let spot = if landed {
+            landing_spot(chunk, transform.translation.round().as_ivec3())
+        } else {
+            None
+        };
+        match spot {
+            Some(pos) => {
+                set_block.send(SetBlock {
+                    pos,
+                    block: falling.block.clone(),
+                    orientation: falling.orientation,
+                    cause: ChangeCause::Fell,
+                });
+            }
+            // buried under a full column or stuck, so it is dropped as an item instead
+            None => spawn_item(
+                falling.block.clone(),
+                &data,
+                &blocks,
+                transform.translation,
+                &mut commands,
+            ),
+        }
+    }
+}
+
+/// Blocks still falling when the chunk is closed are put where they are so they are not lost
+fn land_before_leaving(
+    mut commands: Commands,
+    falling: Query<(Entity, &FallingBlock, &Transform)>,
+    selected: Res<HexSelect>,
+    mut chunks: ResMut<Assets<VoxelChunk>>,
+    mut checks: ResMut<FallChecks>,
+) {
+    let mut landing = std::mem::take(&mut *checks).dropped;
+    for (entity, falling, transform) in &falling {
+        commands.entity(entity).despawn_recursive();
+        let pos = transform.translation.round().as_ivec3();
+        landing.push((
+            pos,
+            FallingBlock::new(falling.block.clone(), falling.orientation, pos),
+        ));
+    }
+    let Some(chunk) = chunks.get_mut(selected.chunk.id()) else {
+        return;
+    };
+    for (pos, falling) in landing {
+        if let Some(pos) = landing_spot(chunk, pos) {
+            chunk.set_oriented(pos, falling.block, falling.orientation);
+        }
+    }
+}
@@ -0,0 +1,483 @@
+//! Liquids flowing through the open chunk, stored beside the blocks in [`VoxelChunk`].
+//!
+//! A voxel holds at most one fluid. Each fixed tick fluids fall into whatever is below that can
+//! hold them, cool where lava or molten metal touches water and then spread sideways towards
+//! neighbours holding less. Pipes and tanks only pass fluid on to other containers and casting
+//! basins cool a full load into a block item.
+
+use bevy::{prelude::*, utils::HashMap};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::{
+    game::{assets::SfxKey, audio::sfx::PlaySfx, HexSelect},
+    screen::{
+        voxel_world::{
+            item::spawn_item,
+            voxel_util::WorldType,
+            voxels::{Block, BlockType, Blocks},
+        },
+        Screen,
+    },
+};
+
+use super::{
+    block_changes::{ChangeCause, SetBlock},
+    VoxelChunk, VoxelId, CHUNK_SIZE, NEIGHBOURS,
+};
+
+/// The amount of fluid that fills an open voxel, a pipe or a casting basin
+pub const FULL: u8 = 8;
+/// The amount of fluid a tank holds
+pub const TANK_CAPACITY: u8 = 64;
+
+const SIDEWAYS: [IVec3; 4] = [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+
+pub(crate) fn fluid_plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (flow_fluids, cast_fluids)
+            .chain()
+            .run_if(in_state(Screen::VoxelWorld)),
+    );
+}
+
+/// Meshes for the fluids in open voxels, kept apart so the flow runs headless
+pub(crate) fn fluid_visuals_plugin(app: &mut App) {
+    app.init_resource::<FluidMaterials>().add_systems(
+        PostUpdate,
+        show_fluids
+            .run_if(in_state(Screen::VoxelWorld).and_then(on_event::<AssetEvent<VoxelChunk>>())),
+    );
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, strum_macros::EnumIter,
+)]
+pub enum Fluid {
+    #[default]
+    Water,
+    Lava,
+    MoltenIron,
+    MoltenCopper,
+    MoltenCobalt,
+}
+
+impl Fluid {
+    /// The fluid a melted block turns into, if it has one
+    pub fn molten(block: &BlockType) -> Option<Fluid> {
+        match block {
+            BlockType::IronBlock => Some(Fluid::MoltenIron),
+            BlockType::CopperBlock => Some(Fluid::MoltenCopper),
+            BlockType::CobaltBlock => Some(Fluid::MoltenCobalt),
+            _ => None,
+        }
+    }
+
+    /// The block this fluid becomes when it cools
+    pub fn solidifies_into(&self) -> Option<BlockType> {
+        match self {
+            Fluid::Water => None,
+            Fluid::Lava => Some(BlockType::Stone),
+            Fluid::MoltenIron => Some(BlockType::IronBlock),
+            Fluid::MoltenCopper => Some(BlockType::CopperBlock),
+            Fluid::MoltenCobalt => Some(BlockType::CobaltBlock),
+        }
+    }
+
+    /// Thick fluids only spread every other tick
+    fn is_viscous(&self) -> bool {
+        *self != Fluid::Water
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Fluid::Water => Color::srgba(0.2, 0.4, 0.9, 0.6),
+            Fluid::Lava => Color::srgba(1.0, 0.3, 0.0, 1.0),
+            Fluid::MoltenIron => Color::srgba(1.0, 0.55, 0.3, 1.0),
+            Fluid::MoltenCopper => Color::srgba(1.0, 0.7, 0.2, 1.0),
+            Fluid::MoltenCobalt => Color::srgba(0.4, 0.5, 1.0, 1.0),
+        }
+    }
+}
+
+/// The fluid in one voxel, empty when `amount` is 0
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FluidCell {
+    pub fluid: Fluid,
+    pub amount: u8,
+}
+
+impl FluidCell {
+    pub const EMPTY: FluidCell = FluidCell {
+        fluid: Fluid::Water,
+        amount: 0,
+    };
+
+    pub fn new(fluid: Fluid, amount: u8) -> FluidCell {
+        FluidCell { fluid, amount }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.amount == 0
+    }
+}
+
+/// How much fluid a block can hold, solid blocks other than containers hold none
+pub fn capacity(block: &BlockType) -> u8 {
+    match block {
+        BlockType::Air | BlockType::Pipe | BlockType::CastingBasin => FULL,
+        BlockType::Tank => TANK_CAPACITY,
+        _ => 0,
+    }
+}
+
+fn is_container(block: &BlockType) -> bool {
+    matches!(
+        block,
+        BlockType::Pipe | BlockType::Tank | BlockType::CastingBasin
+    )
+}
+
+/// How much more of `fluid` fits at `pos`
+fn room(chunk: &VoxelChunk, pos: IVec3, fluid: Fluid) -> u8 {
+    if !VoxelId(pos).in_chunk() {
+        return 0;
+    }
+    let cell = chunk.fluid(pos);
+    if !cell.is_empty() && cell.fluid != fluid {
+        return 0;
+    }
+    capacity(&chunk.get(pos)).saturating_sub(cell.amount)
+}
+
+/// Adds as much of `amount` as fits at `pos` and returns how much that was
+pub fn pour(chunk: &mut VoxelChunk, pos: IVec3, fluid: Fluid, amount: u8) -> u8 {
+    let poured = room(chunk, pos, fluid).min(amount);
+    if poured > 0 {
+        let cell = chunk.fluid(pos);
+        chunk.set_fluid(pos, FluidCell::new(fluid, cell.amount + poured));
+    }
+    poured
+}
+
+/// Pours all of `amount` into a container beside `pos`, false if none of them have room
+pub fn pour_beside(chunk: &mut VoxelChunk, pos: IVec3, fluid: Fluid, amount: u8) -> bool {
+    let Some(outlet) = SIDEWAYS
+        .iter()
+        .map(|offset| pos + *offset)
+        .find(|side| is_container(&chunk.get(*side)) && room(chunk, *side, fluid) >= amount)
+    else {
+        return false;
+    };
+    pour(chunk, outlet, fluid, amount);
+    true
+}
+
+/// Fills a newly generated chunk with the fluids its world has
+pub(super) fn add_world_fluids(world: &WorldType, chunk: &mut VoxelChunk, rng: &mut impl Rng) {
+    let size = CHUNK_SIZE as i32;
+    match world {
+        WorldType::Empty => {}
+        // the dips between the dunes are flooded
+        WorldType::Sand => {
+            for x in 0..size {
+                for y in 0..=8 {
+                    for z in 0..size {
+                        let pos = IVec3::new(x, y, z);
+                        if chunk.get(pos) == BlockType::Air {
+                            chunk.set_fluid(pos, FluidCell::new(Fluid::Water, FULL));
+                        }
+                    }
+                }
+            }
+        }
+        // everything else has a pocket of lava near the bottom
+        _ => {
+            let corner = IVec3::new(
+                rng.gen_range(0..size - 1),
+                rng.gen_range(0..3),
+                rng.gen_range(0..size - 1),
+            );
+            for offset in [IVec3::ZERO, IVec3::X, IVec3::Z, IVec3::new(1, 0, 1)] {
+                chunk.set(corner + offset, BlockType::Air);
+                chunk.set_fluid(corner + offset, FluidCell::new(Fluid::Lava, FULL));
+            }
+        }
+    }
+}
+
+fn flow_fluids(
+    mut tick: Local<u32>,
+    selected: Res<HexSelect>,
+    mut chunks: ResMut<Assets<VoxelChunk>>,
+    mut set_block: EventWriter<SetBlock>,
+) {
+    let Some(chunk) = chunks.get(selected.chunk.id()) else {
+        return;
+    };
+    *tick = tick.wrapping_add(1);
+    // settled fluids leave the chunk alone so it is not marked modified every tick
+    let falls = filled(chunk).any(|pos| {
+        let cell = chunk.fluid(pos);
+        room(chunk, pos - IVec3::Y, cell.fluid) > 0
+    });
+    let planned = (!falls).then(|| plan_flow(chunk, *tick));
+    if planned
+        .as_ref()
+        .is_some_and(|(cooled, moves)| cooled.is_empty() && moves.is_empty())
+    {
+        return;
+    }
+    let Some(chunk) = chunks.get_mut(selected.chunk.id()) else {
+        return;
+    };
+    let size = CHUNK_SIZE as i32;
+
+    // from the bottom up so a column only falls one voxel a tick
+    for y in 0..size {
+        for x in 0..size {
+            for z in 0..size {
+                let pos = IVec3::new(x, y, z);
+                let cell = chunk.fluid(pos);
+                if cell.is_empty() {
+                    continue;
+                }
+                let fallen = pour(chunk, pos - IVec3::Y, cell.fluid, cell.amount);
+                if fallen > 0 {
+                    chunk.set_fluid(pos, FluidCell::new(cell.fluid, cell.amount - fallen));
+                }
+            }
+        }
+    }
+
+    let (cooled, moves) = planned.unwrap_or_else(|| plan_flow(chunk, *tick));
+    for (pos, block) in cooled {
+        chunk.set_fluid(pos, FluidCell::EMPTY);
+        set_block.send(SetBlock {
+            pos,
+            block,
+            orientation: default(),
+            cause: ChangeCause::Fluid,
+        });
+    }
+    for (from, to, fluid) in moves {
+        let cell = chunk.fluid(from);
+        if cell.is_empty() || cell.fluid != fluid {
+            continue;
+        }
+        if pour(chunk, to, fluid, 1) == 1 {
+            chunk.set_fluid(from, FluidCell::new(fluid, cell.amount - 1));
+        }
+    }
+}
+
+/// Every voxel in the chunk holding some fluid
+fn filled(chunk: &VoxelChunk) -> impl Iterator<Item = IVec3> + '_ {
+    let size = CHUNK_SIZE as i32;
+    (0..size)
+        .flat_map(move |x| (0..size).flat_map(move |y| (0..size).map(move |z| IVec3::new(x, y, z))))
+        .filter(|pos| !chunk.fluid(*pos).is_empty())
+}
+
+/// The voxels that cool into blocks and the single units that spread sideways this tick
+fn plan_flow(
+    chunk: &VoxelChunk,
+    tick: u32,
+) -> (Vec<(IVec3, BlockType)>, Vec<(IVec3, IVec3, Fluid)>) {
+    let mut filled = filled(chunk).collect::<Vec<_>>();
+
+    // lava and molten metal in the open cool where they touch water
+    let mut cooled = Vec::new();
+    filled.retain(|pos| {
+        let cell = chunk.fluid(*pos);
+        let Some(block) = cell.fluid.solidifies_into() else {
+            return true;
+        };
+        let touches_water = NEIGHBOURS.iter().any(|offset| {
+            let other = chunk.fluid(*pos + *offset);
+            !other.is_empty() && other.fluid == Fluid::Water
+        });
+        if !touches_water || chunk.get(*pos) != BlockType::Air {
+            return true;
+        }
+        cooled.push((*pos, block));
+        false
+    });
+
+    // every voxel gives one unit to each neighbour that would still hold less than it afterwards
+    let mut moves = Vec::new();
+    for pos in filled {
+        let cell = chunk.fluid(pos);
+        if cell.fluid.is_viscous() && tick % 2 == 1 {
+            continue;
+        }
+        let from = chunk.get(pos);
+        let from_capacity = capacity(&from) as u32;
+        // full pipes and tanks push up into the containers above them
+        let push_up = is_container(&from) && cell.amount as u32 >= from_capacity;
+        let mut left = cell.amount as u32;
+        for offset in SIDEWAYS.iter().chain(push_up.then_some(&IVec3::Y)) {
+            if left == 0 {
+                break;
+            }
+            let to = pos + *offset;
+            if !VoxelId(to).in_chunk() || cooled.iter().any(|(pos, _)| *pos == to) {
+                continue;
+            }
+            let block = chunk.get(to);
+            if is_container(&from) && !is_container(&block) {
+                continue;
+            }
+            let target = chunk.fluid(to);
+            if !target.is_empty() && target.fluid != cell.fluid {
+                continue;
+            }
+            let to_capacity = capacity(&block) as u32;
+            if to_capacity == 0
+                || (target.amount as u32 + 1) * from_capacity > (left - 1) * to_capacity
+            {
+                continue;
+            }
+            left -= 1;
+            moves.push((pos, to, cell.fluid));
+        }
+    }
+    (cooled, moves)
+}
+
+/// Full casting basins cool what they hold into its block, dropped as an item on top of them
+fn cast_fluids(
+    mut commands: Commands,
+    selected: Res<HexSelect>,
+    mut chunks: ResMut<Assets<VoxelChunk>>,
+    data: Res<Assets<Block>>,
+    voxels: Res<Blocks>,
+) {
+    let Some(chunk) = chunks.get(selected.chunk.id()) else {
+        return;
+    };
+    let cast = filled(chunk)
+        .filter(|pos| chunk.get(*pos) == BlockType::CastingBasin)
+        .filter_map(|pos| {
+            let cell = chunk.fluid(pos);
+            let block = cell.fluid.solidifies_into()?;
+            (cell.amount >= FULL).then_some((pos, block))
+        })
+        .collect::<Vec<_>>();
+    if cast.is_empty() {
+        return;
+    }
+    let Some(chunk) = chunks.get_mut(selected.chunk.id()) else {
+        return;
+    };
+    for (pos, block) in cast {
+        chunk.set_fluid(pos, FluidCell::EMPTY);
+        spawn_item(
+            block,
+            &data,
+            &voxels,
+            (pos + IVec3::Y).as_vec3(),
+            &mut commands,
+        );
+        commands.trigger(PlaySfx::Key(SfxKey::Melt).at(pos.as_vec3()));
+    }
+}
+
+#[derive(Resource)]
+struct FluidMaterials {
+    mesh: Handle<Mesh>,
+    materials: HashMap<Fluid, Handle<StandardMaterial>>,
+}
+
+impl FromWorld for FluidMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Cuboid::from_length(1.));
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        let materials = Fluid::iter()
+            .map(|fluid| {
+                let water = fluid == Fluid::Water;
+                let material = materials.add(StandardMaterial {
+                    base_color: fluid.color(),
+                    alpha_mode: if water {
+                        AlphaMode::Blend
+                    } else {
+                        AlphaMode::Opaque
+                    },
+                    // lava and molten metal glow
+                    unlit: !water,
+                    ..Default::default()
+                });
+                (fluid, material)
+            })
+            .collect();
+        FluidMaterials { mesh, materials }
+    }
+}
+
+#[derive(Component)]
+struct FluidVoxel {
+    pos: IVec3,
+    fluid: Fluid,
+}
+
+fn show_fluids(
+    mut commands: Commands,
+    selected: Res<HexSelect>,
+    chunks: Res<Assets<VoxelChunk>>,
+    materials: Res<FluidMaterials>,
+    mut shown: Query<(Entity, &FluidVoxel, &mut Transform)>,
+) {
+    let Some(chunk) = chunks.get(selected.chunk.id()) else {
+        return;
+    };
+    let mut shown = shown
+        .iter_mut()
+        .map(|(entity, voxel, transform)| (voxel.pos, (entity, voxel.fluid, transform)))
+        .collect::<HashMap<_, _>>();
+    let size = CHUNK_SIZE as i32;
+    for x in 0..size {
+        for y in 0..size {
+            for z in 0..size {
+                let pos = IVec3::new(x, y, z);
+                let cell = chunk.fluid(pos);
+                // fluid inside containers is hidden by the container
+                if cell.is_empty() || chunk.get(pos) != BlockType::Air {
+                    continue;
+                }
+                let height = (cell.amount as f32 / FULL as f32).min(1.);
+                let transform =
+                    Transform::from_translation(pos.as_vec3() - Vec3::Y * (1. - height) / 2.)
+                        .with_scale(Vec3::new(1., height, 1.));
+                match shown.remove(&pos) {
+                    Some((_, fluid, mut current)) if fluid == cell.fluid => {
+                        current.set_if_neq(transform);
+                        continue;
+                    }
+                    Some((entity, _, _)) => commands.entity(entity).despawn_recursive(),
+                    None => {}
+                }
+                commands.spawn((
+                    Name::new("Fluid"),
+                    FluidVoxel {
+                        pos,
+                        fluid: cell.fluid,
+                    },
+                    StateScoped(Screen::VoxelWorld),
+                    PbrBundle {
+                        mesh: materials.mesh.clone(),
+                        material: materials.materials[&cell.fluid].clone(),
+                        transform,
+                        ..Default::default()
+                    },
+                ));
+            }
+        }
+    }
+    for (entity, _, _) in shown.into_values() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,90 @@
+//! Translucent previews of blocks that are about to be placed.
+
+use bevy::prelude::*;
+
+use crate::screen::{
+    hex_vox_util::MapDirection,
+    voxel_world::voxels::{Block, BlockType, Blocks},
+    Screen,
+};
+
+pub(crate) fn ghost_plugin(app: &mut App) {
+    app.init_resource::<GhostPreview>()
+        .init_resource::<GhostMaterial>()
+        .add_systems(
+            PostUpdate,
+            sync_ghosts
+                .run_if(in_state(Screen::VoxelWorld).and_then(resource_changed::<GhostPreview>)),
+        )
+        .add_systems(OnExit(Screen::VoxelWorld), clear_preview);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ghost {
+    pub pos: IVec3,
+    pub block: BlockType,
+    /// stored beside `block` the same way [`VoxelChunk`](super::VoxelChunk) keeps its orientations
+    pub direction: MapDirection,
+}
+
+/// Resource of the blocks to show as ghosts, write with `set_if_neq` so they are only rebuilt on change
+#[derive(Resource, Default, PartialEq)]
+pub struct GhostPreview(pub Vec<Ghost>);
+
+#[derive(Resource)]
+struct GhostMaterial(Handle<StandardMaterial>);
+
+impl FromWorld for GhostMaterial {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        GhostMaterial(materials.add(StandardMaterial {
+            base_color: Color::srgba(0.6, 0.8, 1.0, 0.35),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..Default::default()
+        }))
+    }
+}
+
+#[derive(Component)]
+struct GhostBlock;
+
+fn sync_ghosts(
+    mut commands: Commands,
+    preview: Res<GhostPreview>,
+    ghosts: Query<Entity, With<GhostBlock>>,
+    material: Res<GhostMaterial>,
+    blocks: Res<Blocks>,
+    block_data: Res<Assets<Block>>,
+) {
+    for entity in &ghosts {
+        commands.entity(entity).despawn_recursive();
+    }
+    for ghost in preview.0.iter() {
+        if ghost.block == BlockType::Air {
+            continue;
+        }
+        let data = blocks.get(ghost.block.clone());
+        let Some(data) = block_data.get(data.id()) else {
+            continue;
+        };
+        commands.spawn((
+            Name::new("Ghost Block"),
+            GhostBlock,
+            StateScoped(Screen::VoxelWorld),
+            PbrBundle {
+                mesh: data.mesh(),
+                material: material.0.clone(),
+                // a little bigger so it is not hidden inside neighbours
+                transform: Transform::from_translation(ghost.pos.as_vec3())
+                    .with_rotation(ghost.direction.to_rotation())
+                    .with_scale(Vec3::splat(1.02)),
+                ..Default::default()
+            },
+        ));
+    }
+}
+
+fn clear_preview(mut preview: ResMut<GhostPreview>) {
+    preview.0.clear();
+}
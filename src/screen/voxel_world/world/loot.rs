@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::screen::voxel_world::voxels::BlockType;
+
+/// What a block gives when it is broken
+/// set with `drops` in a .block file, blocks without one drop themselves
+#[derive(Debug, Serialize, Deserialize, Clone, Reflect, Default)]
+pub struct LootTable {
+    /// how many times to pick from `entries`
+    #[serde(default = "one")]
+    pub rolls: u32,
+    pub entries: Vec<LootEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Reflect)]
+pub struct LootEntry {
+    /// None is a roll that drops nothing
+    pub item: Option<BlockType>,
+    #[serde(default = "one")]
+    pub weight: u32,
+    #[serde(default = "one")]
+    pub min: u32,
+    #[serde(default = "one")]
+    pub max: u32,
+    /// the tool tier needed for this entry to be picked
+    #[serde(default)]
+    pub tool_tier: u8,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// Resource to choose if broken blocks go straight into the inventory or drop into the world,
+/// saved to the store with the video settings
+#[derive(Resource, Reflect, Default, Debug, Clone, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct DropSettings {
+    pub as_items: bool,
+}
+
+impl LootTable {
+    pub fn single(block: BlockType) -> LootTable {
+        LootTable {
+            rolls: 1,
+            entries: vec![LootEntry {
+                item: Some(block),
+                weight: 1,
+                min: 1,
+                max: 1,
+                tool_tier: 0,
+            }],
+        }
+    }
+
+    /// used when a .block file does not set its own drops
+    pub fn default_for(block: &BlockType) -> LootTable {
+        match block {
+            BlockType::Air => LootTable::default(),
+            BlockType::IronOre | BlockType::CopperOre | BlockType::CobaltOre => LootTable {
+                rolls: 1,
+                entries: vec![
+                    LootEntry {
+                        item: Some(block.clone()),
+                        weight: 1,
+                        min: 1,
+                        max: 1,
+                        tool_tier: 0,
+                    },
+                    LootEntry {
+                        item: Some(block.clone()),
+                        weight: 1,
+                        min: 1,
+                        max: 2,
+                        tool_tier: 1,
+                    },
+                ],
+            },
+            _ => LootTable::single(block.clone()),
+        }
+    }
+
+    /// Rolls the table for a tool of `tier`, returns each item and how many dropped
+    pub fn roll(&self, tier: u8, rng: &mut impl Rng) -> Vec<(BlockType, u32)> {
+        let mut out: Vec<(BlockType, u32)> = Vec::new();
+        let options = self
+            .entries
+            .iter()
+            .filter(|entry| entry.tool_tier <= tier && entry.weight > 0)
+            .collect::<Vec<_>>();
+        let total = options.iter().map(|entry| entry.weight).sum::<u32>();
+        if total == 0 {
+            return out;
+        }
+        for _ in 0..self.rolls {
+            let mut pick = rng.gen_range(0..total);
+            let Some(entry) = options.iter().find(|entry| {
+                if pick < entry.weight {
+                    true
+                } else {
+                    pick -= entry.weight;
+                    false
+                }
+            }) else {
+                continue;
+            };
+            let Some(item) = &entry.item else {
+                continue;
+            };
+            let count = rng.gen_range(entry.min..=entry.max.max(entry.min));
+            if count == 0 {
+                continue;
+            }
+            if let Some((_, total)) = out.iter_mut().find(|(block, _)| block == item) {
+                *total += count;
+            } else {
+                out.push((item.clone(), count));
+            }
+        }
+        out
+    }
+}
+
+#[test]
+fn loot_respects_tool_tier() {
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    let table = LootTable {
+        rolls: 1,
+        entries: vec![LootEntry {
+            item: Some(BlockType::Glass),
+            weight: 1,
+            min: 2,
+            max: 2,
+            tool_tier: 1,
+        }],
+    };
+    assert!(table.roll(0, &mut rng).is_empty());
+    assert_eq!(table.roll(1, &mut rng), vec![(BlockType::Glass, 2)]);
+}
@@ -5,7 +5,10 @@ use std::{
 
 use crate::{
     game::HexSelect,
-    screen::{hex_vox_util::HexId, Screen},
+    screen::{
+        hex_vox_util::{HexId, MapDirection},
+        Screen,
+    },
 };
 use bevy::{
     asset::{
@@ -17,12 +20,13 @@ use bevy::{
 };
 use bevy_pkv::{GetError, PkvStore};
 use block_breaking::block_breaking_plugin;
+use fluid::FluidCell;
 use rand::SeedableRng;
 use serde_big_array::Array;
 
 use super::{
     voxel_util::WorldType,
-    voxels::{Block, BlockType, Blocks},
+    voxels::{Block, BlockType, Blocks, LegacyBlockType},
 };
 
 pub mod voxel_logic;
@@ -63,18 +67,92 @@ impl std::ops::Add for VoxelId {
 }
 
 pub mod block_breaking;
+pub mod block_changes;
+pub mod blueprint;
 pub mod cheats;
+pub mod energy;
+pub mod falling;
+pub mod fluid;
+pub mod ghost;
+pub mod loot;
 pub mod multi_block;
+pub mod signal;
+#[cfg(test)]
+mod simulation;
+pub mod voxel_index;
 
 pub const CHUNK_SIZE: usize = 16;
 pub const BLOCKS_IN_CHUNK: usize = CHUNK_SIZE.pow(3);
 
+/// The offsets to the six voxels sharing a face with a voxel
+pub const NEIGHBOURS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+/// The voxels of a chunk, each with the way it is facing kept beside its [`BlockType`]
 #[derive(Asset, Reflect, serde::Serialize, serde::Deserialize)]
-pub struct VoxelChunk(#[reflect(ignore)] pub Array<BlockType, BLOCKS_IN_CHUNK>);
+pub struct VoxelChunk {
+    #[reflect(ignore)]
+    blocks: Array<BlockType, BLOCKS_IN_CHUNK>,
+    /// only read for blocks where [`BlockType::is_directional`] is true
+    #[reflect(ignore)]
+    orientations: Array<MapDirection, BLOCKS_IN_CHUNK>,
+    /// the signal level of every voxel, see [`signal`]
+    #[reflect(ignore)]
+    #[serde(default = "no_power")]
+    power: Array<u8, BLOCKS_IN_CHUNK>,
+    /// energy held by generators and batteries, see [`energy`]
+    #[reflect(ignore)]
+    #[serde(default = "no_energy")]
+    energy: Array<u32, BLOCKS_IN_CHUNK>,
+    /// the liquid in every voxel, see [`fluid`]
+    #[reflect(ignore)]
+    #[serde(default = "no_fluids")]
+    fluids: Array<FluidCell, BLOCKS_IN_CHUNK>,
+}
+
+fn no_power() -> Array<u8, BLOCKS_IN_CHUNK> {
+    Array([0; BLOCKS_IN_CHUNK])
+}
+
+fn no_energy() -> Array<u32, BLOCKS_IN_CHUNK> {
+    Array([0; BLOCKS_IN_CHUNK])
+}
+
+fn no_fluids() -> Array<FluidCell, BLOCKS_IN_CHUNK> {
+    Array([FluidCell::EMPTY; BLOCKS_IN_CHUNK])
+}
+
+/// The chunk format from before orientations were split from [`BlockType`]
+#[derive(serde::Deserialize)]
+struct LegacyVoxelChunk(Array<LegacyBlockType, BLOCKS_IN_CHUNK>);
+
+impl From<LegacyVoxelChunk> for VoxelChunk {
+    fn from(legacy: LegacyVoxelChunk) -> Self {
+        let mut chunk = VoxelChunk::new();
+        for (index, block) in legacy.0.iter().enumerate() {
+            let (block, orientation) = block.clone().migrate();
+            chunk.blocks[index] = block;
+            chunk.orientations[index] = orientation;
+        }
+        chunk
+    }
+}
 
 impl VoxelChunk {
     pub fn new() -> VoxelChunk {
-        VoxelChunk(Array(std::array::from_fn(|_| BlockType::default())))
+        VoxelChunk {
+            blocks: Array(std::array::from_fn(|_| BlockType::default())),
+            orientations: Array([MapDirection::default(); BLOCKS_IN_CHUNK]),
+            power: no_power(),
+            energy: no_energy(),
+            fluids: no_fluids(),
+        }
     }
 
     fn from_hex(hex: &WorldType, rng: &mut impl rand::Rng) -> VoxelChunk {
@@ -90,10 +168,11 @@ impl VoxelChunk {
                 }
             }
         }
+        fluid::add_world_fluids(hex, &mut chunk, rng);
         chunk
     }
 
-    pub fn set(&mut self, pos: IVec3, block: BlockType) -> BlockType {
+    fn index(pos: IVec3) -> Option<usize> {
         if pos.x >= CHUNK_SIZE as i32
             || pos.x < 0
             || pos.y >= CHUNK_SIZE as i32
@@ -101,13 +180,41 @@ impl VoxelChunk {
             || pos.z >= CHUNK_SIZE as i32
             || pos.z < 0
         {
-            return BlockType::Air;
+            return None;
         }
-        let index =
-            pos.x as usize + pos.z as usize * CHUNK_SIZE + pos.y as usize * CHUNK_SIZE.pow(2);
-        let old = self.0[index].clone();
-        self.0[index] = block;
-        old
+        Some(pos.x as usize + pos.z as usize * CHUNK_SIZE + pos.y as usize * CHUNK_SIZE.pow(2))
+    }
+
+    /// every block in the chunk in storage order
+    pub fn blocks(&self) -> &[BlockType] {
+        &self.blocks.0
+    }
+
+    /// sets a block facing up
+    pub fn set(&mut self, pos: IVec3, block: BlockType) -> BlockType {
+        self.set_oriented(pos, block, MapDirection::default())
+    }
+
+    pub fn set_oriented(
+        &mut self,
+        pos: IVec3,
+        block: BlockType,
+        orientation: MapDirection,
+    ) -> BlockType {
+        let Some(index) = VoxelChunk::index(pos) else {
+            return BlockType::Air;
+        };
+        self.orientations[index] = if block.is_directional() {
+            orientation
+        } else {
+            MapDirection::default()
+        };
+        self.power[index] = 0;
+        self.energy[index] = 0;
+        // solid blocks push fluid out, containers keep what fits
+        let fluid = &mut self.fluids[index];
+        fluid.amount = fluid.amount.min(fluid::capacity(&block));
+        std::mem::replace(&mut self.blocks[index], block)
     }
 
     pub fn get(&self, pos: IVec3) -> BlockType {
@@ -125,19 +232,97 @@ impl VoxelChunk {
         if index >= CHUNK_SIZE.pow(3) {
             return BlockType::Air;
         }
-        self.0[index].clone()
+        self.blocks[index].clone()
+    }
+
+    /// the way the voxel at `pos` is facing, up for anything outside the chunk
+    pub fn orientation(&self, pos: IVec3) -> MapDirection {
+        VoxelChunk::index(pos)
+            .map(|index| self.orientations[index])
+            .unwrap_or_default()
+    }
+
+    /// the signal level at `pos`, nothing outside the chunk has power
+    pub fn power(&self, pos: IVec3) -> u8 {
+        VoxelChunk::index(pos)
+            .map(|index| self.power[index])
+            .unwrap_or_default()
+    }
+
+    pub fn set_power(&mut self, pos: IVec3, power: u8) {
+        if let Some(index) = VoxelChunk::index(pos) {
+            self.power[index] = power;
+        }
+    }
+
+    /// the energy stored in the block at `pos`
+    pub fn energy(&self, pos: IVec3) -> u32 {
+        VoxelChunk::index(pos)
+            .map(|index| self.energy[index])
+            .unwrap_or_default()
+    }
+
+    pub fn set_energy(&mut self, pos: IVec3, energy: u32) {
+        if let Some(index) = VoxelChunk::index(pos) {
+            self.energy[index] = energy;
+        }
+    }
+
+    /// the fluid at `pos`, nothing outside the chunk holds any
+    pub fn fluid(&self, pos: IVec3) -> FluidCell {
+        VoxelChunk::index(pos)
+            .map(|index| self.fluids[index])
+            .unwrap_or(FluidCell::EMPTY)
+    }
+
+    pub fn set_fluid(&mut self, pos: IVec3, fluid: FluidCell) {
+        if let Some(index) = VoxelChunk::index(pos) {
+            self.fluids[index] = fluid;
+        }
     }
 }
 
+#[test]
+fn legacy_chunk_keeps_orientation() {
+    let mut legacy = LegacyVoxelChunk(Array(std::array::from_fn(|_| LegacyBlockType::Air)));
+    legacy.0[0] = LegacyBlockType::Drill(MapDirection::North);
+    legacy.0[1] = LegacyBlockType::Conveyor(MapDirection::East);
+    legacy.0[2] = LegacyBlockType::Stone;
+    let chunk = VoxelChunk::from(legacy);
+    assert_eq!(chunk.get(IVec3::ZERO), BlockType::Drill);
+    assert_eq!(chunk.orientation(IVec3::ZERO), MapDirection::North);
+    assert_eq!(chunk.get(IVec3::X), BlockType::Conveyor);
+    assert_eq!(chunk.orientation(IVec3::X), MapDirection::East);
+    assert_eq!(chunk.get(IVec3::X * 2), BlockType::Stone);
+    assert_eq!(chunk.orientation(IVec3::X * 2), MapDirection::Up);
+}
+
 pub(crate) fn voxel_world(app: &mut App) {
     block_breaking_plugin(app);
+    block_changes::block_changes_plugin(app);
+    voxel_index::voxel_index_plugin(app);
+    ghost::ghost_plugin(app);
+    blueprint::blueprint_plugin(app);
+    signal::signal_plugin(app);
+    energy::energy_plugin(app);
+    energy::energy_overlay_plugin(app);
+    fluid::fluid_plugin(app);
+    fluid::fluid_visuals_plugin(app);
+    falling::falling_plugin(app);
     app.init_resource::<VoxelStore>();
+    app.init_resource::<loot::DropSettings>()
+        .register_type::<loot::DropSettings>();
     app.init_asset::<VoxelChunk>()
-        .init_resource::<multi_block::MultiBlocks>();
+        .init_resource::<multi_block::MultiBlocks>()
+        .init_resource::<multi_block::MultiBlockOrigins>()
+        .init_resource::<multi_block::MultiBlockCheck>();
     app.init_asset_loader::<VoxelChunkLoader>();
     app.add_systems(
         FixedUpdate,
-        multi_block::check_for_multi_blocks.run_if(in_state(Screen::VoxelWorld)),
+        multi_block::check_for_multi_blocks.run_if(
+            in_state(Screen::VoxelWorld)
+                .and_then(resource_equals(multi_block::MultiBlockCheck(true))),
+        ),
     );
     app.add_systems(
         Update,
@@ -145,7 +330,10 @@ pub(crate) fn voxel_world(app: &mut App) {
     );
     app.add_systems(
         OnEnter(Screen::VoxelWorld),
-        (load_chunk, open_loaded_world).run_if(resource_changed::<HexSelect>),
+        (
+            (load_chunk, open_loaded_world).run_if(resource_changed::<HexSelect>),
+            multi_block::recheck_multi_blocks,
+        ),
     );
     app.add_plugins(voxel_logic::VoxelLogic);
     #[cfg(feature = "dev")]
@@ -170,11 +358,16 @@ fn open_loaded_world(
     }
 }
 
+/// The entity every voxel in the open chunk is a child of
+#[derive(Component)]
+pub struct ChunkRoot;
+
 fn fill_world(chunk: &VoxelChunk, commands: &mut Commands, blocks: &Blocks, data: &Assets<Block>) {
     commands
         .spawn((
             SpatialBundle::default(),
             Name::new("Chunk"),
+            ChunkRoot,
             StateScoped(crate::screen::Screen::VoxelWorld),
         ))
         .with_children(|commands| {
@@ -183,7 +376,7 @@ fn fill_world(chunk: &VoxelChunk, commands: &mut Commands, blocks: &Blocks, data
                     for z in 0..CHUNK_SIZE as i32 {
                         let id = IVec3::new(x, y, z);
                         let block = chunk.get(id);
-                        spawn_voxel(block, blocks, id, commands, data);
+                        spawn_voxel(block, chunk.orientation(id), blocks, id, commands, data);
                     }
                 }
             }
@@ -211,6 +404,7 @@ fn fill_world_after_load(
 
 fn spawn_voxel(
     block: BlockType,
+    orientation: MapDirection,
     voxels: &Blocks,
     offset: IVec3,
     commands: &mut ChildBuilder,
@@ -219,7 +413,6 @@ fn spawn_voxel(
     if block == BlockType::Air {
         return;
     };
-    let direction = block.direction();
     let data = voxels.get(block);
     let data = voxel_data.get(data.id()).expect("all blocks loaded");
     let mut entity = commands.spawn((
@@ -229,7 +422,7 @@ fn spawn_voxel(
             mesh: data.mesh(),
             material: data.material(),
             transform: Transform::from_translation(offset.as_vec3())
-                .with_rotation(direction.to_rotation()),
+                .with_rotation(orientation.to_rotation()),
             ..Default::default()
         },
     ));
@@ -313,10 +506,16 @@ impl AssetLoader for VoxelChunkLoader {
                     let chunk = VoxelChunk::from_hex(settings, &mut rng);
                     Ok(chunk)
                 }
-                Err(e) => {
-                    error!("{}", e);
-                    Err(std::io::Error::new(ErrorKind::Unsupported, "pkv error"))
-                }
+                Err(e) => match lock.get::<LegacyVoxelChunk>(path) {
+                    Ok(legacy) => {
+                        info!("Migrated chunk {path} to store orientations");
+                        Ok(VoxelChunk::from(legacy))
+                    }
+                    Err(_) => {
+                        error!("{}", e);
+                        Err(std::io::Error::new(ErrorKind::Unsupported, "pkv error"))
+                    }
+                },
             }
         }
     }
@@ -1,9 +1,16 @@
 use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
 
 use crate::game::HexSelect;
+use crate::screen::hex_vox_util::MapDirection;
+use crate::screen::quests::{QuestEvent, UnlockedRecipes};
+use crate::screen::voxel_world::item::spawn_item;
 use crate::screen::voxel_world::voxels::{Block, Blocks};
 
-use super::{VoxelChunk, VoxelId, CHUNK_SIZE};
+use super::{
+    block_changes::{BlockChanged, ChangeCause, SetBlock},
+    VoxelChunk, CHUNK_SIZE,
+};
 
 use super::super::voxels::BlockType;
 
@@ -12,6 +19,44 @@ pub struct MultiBlocks {
     recipes: HashMap<MultiBlockType, MultiBlockRecipe>,
 }
 
+impl MultiBlocks {
+    #[cfg(test)]
+    pub fn types(&self) -> impl Iterator<Item = MultiBlockType> + '_ {
+        self.recipes.keys().copied()
+    }
+
+    /// The block a recipe places, if it builds a machine
+    pub fn machine(&self, recipe: MultiBlockType) -> Option<BlockType> {
+        match &self.recipes.get(&recipe)?.output_block {
+            MultiOutput::Specific(block) | MultiOutput::Oriented(block, _) => Some(block.clone()),
+            MultiOutput::Melt(_) | MultiOutput::Item(_) => None,
+        }
+    }
+
+    /// The blocks a machine is built from, laid out like the rules of its recipe, with
+    /// any block standing in where a rule allows more than one
+    pub fn preview(&self, machine: &BlockType) -> Option<(IVec3, Vec<BlockType>)> {
+        let recipe = self.recipes.values().find(|recipe| {
+            matches!(
+                &recipe.output_block,
+                MultiOutput::Specific(block) | MultiOutput::Oriented(block, _) if block == machine
+            )
+        })?;
+        let blocks = recipe
+            .rules
+            .iter()
+            .map(|rule| match rule {
+                MultiBlockRule::Solid => BlockType::Stone,
+                MultiBlockRule::Specific(block) => block.clone(),
+                MultiBlockRule::Empty => BlockType::Air,
+                MultiBlockRule::CanMelt => BlockType::IronOre,
+                MultiBlockRule::Fuel => BlockType::Coal,
+            })
+            .collect();
+        Some((recipe.size, blocks))
+    }
+}
+
 impl FromWorld for MultiBlocks {
     fn from_world(_world: &mut World) -> Self {
         let mut map: bevy::utils::hashbrown::HashMap<MultiBlockType, MultiBlockRecipe> =
@@ -104,9 +149,7 @@ impl FromWorld for MultiBlocks {
                     MultiBlockRule::Empty,
                     MultiBlockRule::Empty,
                 ],
-                output_block: MultiOutput::Specific(BlockType::Drill(
-                    crate::screen::hex_vox_util::MapDirection::Down,
-                )),
+                output_block: MultiOutput::Oriented(BlockType::Drill, MapDirection::Down),
                 output_offset: IVec3 { x: 1, y: 1, z: 1 },
                 output_clear: vec![ClearType::All],
             },
@@ -182,9 +225,7 @@ impl FromWorld for MultiBlocks {
                     MultiBlockRule::Specific(BlockType::CopperBlock),
                     MultiBlockRule::Specific(BlockType::CopperBlock),
                 ],
-                output_block: MultiOutput::Specific(BlockType::Piston(
-                    crate::screen::hex_vox_util::MapDirection::Down,
-                )),
+                output_block: MultiOutput::Oriented(BlockType::Piston, MapDirection::Down),
                 output_offset: IVec3 { x: 1, y: 1, z: 1 },
                 output_clear: vec![ClearType::All],
             },
@@ -222,13 +263,141 @@ impl FromWorld for MultiBlocks {
                     MultiBlockRule::Specific(BlockType::CobaltBlock),
                     MultiBlockRule::Specific(BlockType::CobaltBlock),
                 ],
-                output_block: MultiOutput::Specific(BlockType::PistonL2(
-                    crate::screen::hex_vox_util::MapDirection::Down,
-                )),
+                output_block: MultiOutput::Oriented(BlockType::PistonL2, MapDirection::Down),
                 output_offset: IVec3 { x: 1, y: 1, z: 1 },
                 output_clear: vec![ClearType::All],
             },
         );
+        for (recipe, metal, pickaxe) in [
+            (
+                MultiBlockType::IronPickaxe,
+                BlockType::IronBlock,
+                BlockType::IronPickaxe,
+            ),
+            (
+                MultiBlockType::CopperPickaxe,
+                BlockType::CopperBlock,
+                BlockType::CopperPickaxe,
+            ),
+            (
+                MultiBlockType::CobaltPickaxe,
+                BlockType::CobaltBlock,
+                BlockType::CobaltPickaxe,
+            ),
+        ] {
+            map.insert(
+                recipe,
+                MultiBlockRecipe {
+                    size: IVec3::new(3, 2, 1),
+                    rules: vec![
+                        MultiBlockRule::Empty,
+                        MultiBlockRule::Specific(BlockType::Stone),
+                        MultiBlockRule::Empty,
+                        MultiBlockRule::Specific(metal.clone()),
+                        MultiBlockRule::Specific(metal.clone()),
+                        MultiBlockRule::Specific(metal),
+                    ],
+                    output_block: MultiOutput::Item(pickaxe),
+                    output_offset: IVec3::new(1, 1, 0),
+                    output_clear: vec![ClearType::All],
+                },
+            );
+        }
+        // the blocks machines are wired up, powered and piped with, laid out in rows along x
+        // from the bottom up
+        use BlockType::{Air, Coal, CopperBlock, Glass, IronBlock, Stone};
+        for (recipe, rows, item) in [
+            (
+                MultiBlockType::Wire,
+                vec![[Glass, IronBlock, Glass]],
+                BlockType::Wire,
+            ),
+            (
+                MultiBlockType::Lever,
+                vec![[Stone, IronBlock, Stone], [Glass, Stone, Glass]],
+                BlockType::Lever,
+            ),
+            (
+                MultiBlockType::Button,
+                vec![[Stone, IronBlock, Stone], [Stone, Glass, Stone]],
+                BlockType::Button,
+            ),
+            (
+                MultiBlockType::PressurePlate,
+                vec![[Stone, Glass, Stone], [IronBlock, IronBlock, IronBlock]],
+                BlockType::PressurePlate,
+            ),
+            (
+                MultiBlockType::ItemDetector,
+                vec![[Stone, IronBlock, Stone], [IronBlock, Glass, IronBlock]],
+                BlockType::ItemDetector,
+            ),
+            (
+                MultiBlockType::NotGate,
+                vec![[IronBlock, Stone, IronBlock], [Stone, Glass, Stone]],
+                BlockType::NotGate,
+            ),
+            (
+                MultiBlockType::AndGate,
+                vec![[IronBlock, Stone, IronBlock], [Glass, Stone, Glass]],
+                BlockType::AndGate,
+            ),
+            (
+                MultiBlockType::Cable,
+                vec![[Glass, CopperBlock, Glass]],
+                BlockType::Cable,
+            ),
+            (
+                MultiBlockType::Generator,
+                vec![
+                    [IronBlock, Coal, IronBlock],
+                    [IronBlock, CopperBlock, IronBlock],
+                ],
+                BlockType::Generator,
+            ),
+            (
+                MultiBlockType::Battery,
+                vec![
+                    [IronBlock, CopperBlock, IronBlock],
+                    [IronBlock, CopperBlock, IronBlock],
+                ],
+                BlockType::Battery,
+            ),
+            (
+                MultiBlockType::Pipe,
+                vec![[Glass, Glass, Glass]],
+                BlockType::Pipe,
+            ),
+            (
+                MultiBlockType::Tank,
+                vec![[IronBlock, Glass, IronBlock], [IronBlock, Glass, IronBlock]],
+                BlockType::Tank,
+            ),
+            (
+                MultiBlockType::CastingBasin,
+                vec![
+                    [IronBlock, IronBlock, IronBlock],
+                    [IronBlock, Air, IronBlock],
+                ],
+                BlockType::CastingBasin,
+            ),
+        ] {
+            let height = rows.len() as i32;
+            map.insert(
+                recipe,
+                MultiBlockRecipe {
+                    size: IVec3::new(3, height, 1),
+                    rules: rows
+                        .into_iter()
+                        .flatten()
+                        .map(MultiBlockRule::Specific)
+                        .collect(),
+                    output_block: MultiOutput::Item(item),
+                    output_offset: IVec3::new(1, height - 1, 0),
+                    output_clear: vec![ClearType::All],
+                },
+            );
+        }
         MultiBlocks { recipes: map }
     }
 }
@@ -243,7 +412,11 @@ enum MultiBlockRule {
 
 enum MultiOutput {
     Specific(BlockType),
+    /// a machine built facing a direction
+    Oriented(BlockType, MapDirection),
     Melt(IVec3),
+    /// drop an item instead of placing a block
+    Item(BlockType),
 }
 
 impl MultiBlockRule {
@@ -258,14 +431,30 @@ impl MultiBlockRule {
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
-enum MultiBlockType {
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum MultiBlockType {
     Furnace,
     Smelt,
     Drill,
     Score,
     Piston,
     PistonL2,
+    IronPickaxe,
+    CopperPickaxe,
+    CobaltPickaxe,
+    Wire,
+    Lever,
+    Button,
+    PressurePlate,
+    ItemDetector,
+    NotGate,
+    AndGate,
+    Cable,
+    Generator,
+    Battery,
+    Pipe,
+    Tank,
+    CastingBasin,
 }
 
 struct MultiBlockRecipe {
@@ -277,75 +466,83 @@ struct MultiBlockRecipe {
 }
 
 impl MultiBlockRecipe {
-    pub fn clear(
-        &self,
-        pos: IVec3,
-        chunk: &mut VoxelChunk,
-        commands: &mut Commands,
-        blocks: &Query<(Entity, &VoxelId)>,
-    ) {
+    /// every position that is emptied when this recipe is built at `pos`
+    pub fn cleared(&self, pos: IVec3) -> Vec<IVec3> {
+        let mut out = Vec::new();
         for clear in self.output_clear.iter() {
-            clear.apply(self, pos, chunk, commands, blocks);
+            clear.positions(self, pos, &mut out);
         }
+        out
     }
 
     pub fn output(
         &self,
         pos: IVec3,
-        chunk: &VoxelChunk,
+        chunk: &impl Fn(IVec3) -> BlockType,
         voxel_data: &Assets<Block>,
         voxels: &Blocks,
-    ) -> Vec<(IVec3, BlockType)> {
+    ) -> Vec<(IVec3, BlockType, MapDirection)> {
         let mut out = Vec::new();
         match &self.output_block {
-            MultiOutput::Specific(block) => out.push((pos + self.output_offset, block.clone())),
+            MultiOutput::Specific(block) => out.push((
+                pos + self.output_offset,
+                block.clone(),
+                MapDirection::default(),
+            )),
+            MultiOutput::Oriented(block, orientation) => {
+                out.push((pos + self.output_offset, block.clone(), *orientation))
+            }
             MultiOutput::Melt(offset) => {
-                let block = chunk.get(pos + *offset);
+                let block = chunk(pos + *offset);
                 let block = voxels.get(block);
                 let block = voxel_data.get(block.id()).expect("all blocks loaded");
                 if let Some(melt) = block.melt() {
-                    out.push((pos + *offset, melt));
+                    out.push((pos + *offset, melt, MapDirection::default()));
                 }
             }
+            MultiOutput::Item(_) => {}
         };
         out
     }
+
+    /// machines that are placed as a block can be dismantled for their ingredients
+    fn refundable(&self) -> bool {
+        matches!(
+            self.output_block,
+            MultiOutput::Specific(_) | MultiOutput::Oriented(..)
+        )
+    }
+
+    /// every block that [`MultiBlockRecipe::cleared`] will remove
+    fn ingredients(&self, pos: IVec3, chunk: &impl Fn(IVec3) -> BlockType) -> Vec<BlockType> {
+        self.cleared(pos)
+            .into_iter()
+            .map(chunk)
+            .filter(|block| block != &BlockType::Air)
+            .collect()
+    }
+
+    pub fn output_item(&self) -> Option<BlockType> {
+        match &self.output_block {
+            MultiOutput::Item(item) => Some(item.clone()),
+            _ => None,
+        }
+    }
 }
 
 impl ClearType {
-    fn apply(
-        &self,
-        recipe: &MultiBlockRecipe,
-        pos: IVec3,
-        chunk: &mut VoxelChunk,
-        commands: &mut Commands,
-        blocks: &Query<(Entity, &VoxelId)>,
-    ) {
+    fn positions(&self, recipe: &MultiBlockRecipe, pos: IVec3, out: &mut Vec<IVec3>) {
         match self {
             ClearType::All => {
                 for rx in 0..recipe.size.x {
                     for ry in 0..recipe.size.y {
                         for rz in 0..recipe.size.z {
-                            let pos = pos + IVec3::new(rx, ry, rz);
-                            chunk.set(pos, BlockType::Air);
-                            for (entity, id) in blocks {
-                                if id.0 == pos {
-                                    commands.entity(entity).despawn_recursive();
-                                }
-                            }
+                            out.push(pos + IVec3::new(rx, ry, rz));
                         }
                     }
                 }
             }
-            ClearType::Offset(offset) => {
-                let pos = pos + *offset;
-                chunk.set(pos, BlockType::Air);
-                for (entity, id) in blocks {
-                    if id.0 == pos {
-                        commands.entity(entity).despawn_recursive();
-                    }
-                }
-            }
+            ClearType::Offset(offset) => out.push(pos + *offset),
         }
     }
 }
@@ -355,31 +552,134 @@ enum ClearType {
     Offset(IVec3),
 }
 
+/// What was used to build a machine
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MultiBlockOrigin {
+    pub pos: IVec3,
+    pub recipe: MultiBlockType,
+    pub ingredients: Vec<BlockType>,
+}
+
+impl MultiBlockOrigin {
+    /// the ingredients grouped into stacks
+    pub fn refund(&self) -> Vec<(BlockType, u32)> {
+        let mut out: Vec<(BlockType, u32)> = Vec::new();
+        for block in self.ingredients.iter() {
+            if let Some((_, count)) = out.iter_mut().find(|(other, _)| other == block) {
+                *count += 1;
+            } else {
+                out.push((block.clone(), 1));
+            }
+        }
+        out
+    }
+}
+
+/// Resource tracking which recipe made each machine in the open chunk
+#[derive(Resource, Default)]
+pub struct MultiBlockOrigins(HashMap<IVec3, MultiBlockOrigin>);
+
+impl MultiBlockOrigins {
+    pub fn insert(&mut self, origin: MultiBlockOrigin) {
+        self.0.insert(origin.pos, origin);
+    }
+
+    pub fn take(&mut self, pos: IVec3) -> Option<MultiBlockOrigin> {
+        self.0.remove(&pos)
+    }
+
+    /// list form for saving, maps with vector keys do not work on web
+    pub fn to_saved(&self) -> Vec<MultiBlockOrigin> {
+        self.0.values().cloned().collect()
+    }
+
+    pub fn from_saved(saved: Vec<MultiBlockOrigin>) -> MultiBlockOrigins {
+        MultiBlockOrigins(
+            saved
+                .into_iter()
+                .map(|origin| (origin.pos, origin))
+                .collect(),
+        )
+    }
+}
+
+/// Forgets machines that were replaced by anything but the player breaking them, so whatever
+/// takes their place does not refund the machine again when it is broken
+pub fn forget_replaced_machines(
+    mut changed: EventReader<BlockChanged>,
+    mut origins: ResMut<MultiBlockOrigins>,
+    recipes: Res<MultiBlocks>,
+) {
+    // a position can change more than once a frame, only the last change is what stays there
+    let mut last = HashMap::new();
+    for change in changed.read() {
+        last.insert(change.pos, change);
+    }
+    for (pos, change) in last {
+        if change.cause == ChangeCause::Broken {
+            continue;
+        }
+        let Some(origin) = origins.0.get(&pos) else {
+            continue;
+        };
+        if recipes.machine(origin.recipe).as_ref() != Some(&change.new) {
+            origins.take(pos);
+        }
+    }
+}
+
+/// Resource set when the chunk has changed and may hold a new multi block
+#[derive(Resource, Default, PartialEq)]
+pub struct MultiBlockCheck(pub bool);
+
+pub fn recheck_multi_blocks(mut check: ResMut<MultiBlockCheck>) {
+    check.0 = true;
+}
+
+pub fn mark_multi_blocks(
+    mut check: ResMut<MultiBlockCheck>,
+    mut changed: EventReader<BlockChanged>,
+) {
+    if changed.read().count() > 0 {
+        check.0 = true;
+    }
+}
+
 pub fn check_for_multi_blocks(
-    mut chunk_data: ResMut<Assets<VoxelChunk>>,
-    blocks: Query<(Entity, &VoxelId)>,
+    chunk_data: Res<Assets<VoxelChunk>>,
     recipes: Res<MultiBlocks>,
+    mut origins: ResMut<MultiBlockOrigins>,
+    mut check: ResMut<MultiBlockCheck>,
+    mut set_block: EventWriter<SetBlock>,
+    unlocked: Res<UnlockedRecipes>,
     selected: Res<HexSelect>,
     voxels: Res<Blocks>,
     voxel_data: Res<Assets<Block>>,
     mut commands: Commands,
 ) {
-    let Some(chunk) = chunk_data.get_mut(selected.chunk.id()) else {
+    let Some(chunk) = chunk_data.get(selected.chunk.id()) else {
         error!("chunk not loaded");
         return;
     };
-    for (_, recipe) in recipes.recipes.iter() {
+    check.0 = false;
+    // edits are applied after this system so track what has been changed this run
+    let mut edits: HashMap<IVec3, BlockType> = HashMap::new();
+    for (recipe_type, recipe) in recipes.recipes.iter() {
+        if !unlocked.contains(recipe_type) {
+            continue;
+        }
         for x in 0..CHUNK_SIZE as i32 {
             for y in 0..CHUNK_SIZE as i32 {
                 'failed: for z in 0..CHUNK_SIZE as i32 {
+                    let get =
+                        |pos: IVec3| edits.get(&pos).cloned().unwrap_or_else(|| chunk.get(pos));
                     for rx in 0..recipe.size.x {
                         for ry in 0..recipe.size.y {
                             for rz in 0..recipe.size.z {
                                 let pos = IVec3::new(x, y, z) + IVec3::new(rx, ry, rz);
                                 let rule_index =
                                     rx + rz * recipe.size.x + ry * recipe.size.x * recipe.size.z;
-                                let block = chunk.get(pos);
-                                let block = voxels.get(block);
+                                let block = voxels.get(get(pos));
                                 let block = voxel_data.get(block.id()).expect("all BLocks loaded");
                                 if !recipe.rules[rule_index as usize].applies_to(block) {
                                     continue 'failed;
@@ -387,25 +687,42 @@ pub fn check_for_multi_blocks(
                             }
                         }
                     }
-                    let out = recipe.output(IVec3::new(x, y, z), chunk, &voxel_data, &voxels);
-                    recipe.clear(IVec3::new(x, y, z), chunk, &mut commands, &blocks);
-                    for (pos, block) in out {
-                        let data = voxels.get(block.clone());
-                        let data = voxel_data.get(data.id()).expect("all block loaded");
-                        let mut entity = commands.spawn((
-                            Name::new("Multi Block"),
-                            VoxelId(pos),
-                            PbrBundle {
-                                mesh: data.mesh(),
-                                material: data.material(),
-                                transform: Transform::from_translation(pos.as_vec3()),
-                                ..Default::default()
-                            },
-                        ));
-                        if data.is_solid() {
-                            entity.insert(bevy_rapier3d::prelude::Collider::cuboid(0.5, 0.5, 0.5));
+                    let out = recipe.output(IVec3::new(x, y, z), &get, &voxel_data, &voxels);
+                    let ingredients = recipe.ingredients(IVec3::new(x, y, z), &get);
+                    commands.trigger(QuestEvent::Built(*recipe_type));
+                    for pos in recipe.cleared(IVec3::new(x, y, z)) {
+                        edits.insert(pos, BlockType::Air);
+                        set_block.send(SetBlock {
+                            pos,
+                            block: BlockType::Air,
+                            orientation: MapDirection::default(),
+                            cause: ChangeCause::MultiBlock,
+                        });
+                    }
+                    if let Some(item) = recipe.output_item() {
+                        spawn_item(
+                            item,
+                            &voxel_data,
+                            &voxels,
+                            (IVec3::new(x, y, z) + recipe.output_offset).as_vec3(),
+                            &mut commands,
+                        );
+                    }
+                    for (pos, block, orientation) in out {
+                        edits.insert(pos, block.clone());
+                        set_block.send(SetBlock {
+                            pos,
+                            block,
+                            orientation,
+                            cause: ChangeCause::MultiBlock,
+                        });
+                        if recipe.refundable() {
+                            origins.insert(MultiBlockOrigin {
+                                pos,
+                                recipe: *recipe_type,
+                                ingredients: ingredients.clone(),
+                            });
                         }
-                        chunk.set(pos, block);
                     }
                 }
             }
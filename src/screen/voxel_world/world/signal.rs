@@ -0,0 +1,294 @@
+//! Power carried through the open chunk from switches and sensors along wires.
+//!
+//! Each fixed tick sensors check what is around them, gates read what their inputs were on the
+//! tick before and wires take the strongest neighbour minus one. Machines touching anything that
+//! gives them power get [`Powered`] and do nothing without it, unless nothing that could power
+//! them is next to them at all, so machines nobody wired up keep running.
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    game::{assets::SfxKey, audio::sfx::PlaySfx, HexSelect},
+    screen::{
+        voxel_world::{item::Item, voxel_util::VoxelPlayer, voxels::BlockType},
+        Screen,
+    },
+};
+
+use super::{
+    voxel_logic::{Conveyor, Extractor, Melter, Piston},
+    VoxelChunk, VoxelId, CHUNK_SIZE, NEIGHBOURS,
+};
+
+/// The power a source gives out, wires lose one for every block they cross
+pub const MAX_POWER: u8 = 15;
+
+pub(crate) fn signal_plugin(app: &mut App) {
+    app.add_event::<UseBlock>()
+        .add_systems(Update, use_blocks.run_if(in_state(Screen::VoxelWorld)))
+        .add_systems(
+            FixedUpdate,
+            (sense_blocks, update_signals)
+                .chain()
+                .in_set(SignalUpdate)
+                .run_if(in_state(Screen::VoxelWorld)),
+        );
+}
+
+/// Machines run after this so they see the power from the same tick
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SignalUpdate;
+
+/// Send this when the player uses the block at a position
+#[derive(Event, Debug, Clone, Copy)]
+pub struct UseBlock(pub IVec3);
+
+/// Marks machines that have power this tick
+#[derive(Component)]
+pub struct Powered;
+
+/// Blocks the player uses instead of placing against
+pub fn is_switch(block: &BlockType) -> bool {
+    matches!(block, BlockType::Lever | BlockType::Button)
+}
+
+fn chunk_positions() -> impl Iterator<Item = IVec3> {
+    let size = CHUNK_SIZE as i32;
+    (0..size)
+        .flat_map(move |x| (0..size).flat_map(move |y| (0..size).map(move |z| IVec3::new(x, y, z))))
+}
+
+/// The neighbours of `pos` inside the chunk, [`VoxelChunk::get`] wraps past the far edges
+fn neighbours(pos: IVec3) -> impl Iterator<Item = IVec3> {
+    NEIGHBOURS
+        .iter()
+        .map(move |offset| pos + *offset)
+        .filter(|neighbour| VoxelId(*neighbour).in_chunk())
+}
+
+/// The power the block at `from` gives to the neighbour at `to`, nothing outside the chunk
+/// gives power
+fn output(chunk: &VoxelChunk, power: &impl Fn(IVec3) -> u8, from: IVec3, to: IVec3) -> u8 {
+    if !VoxelId(from).in_chunk() {
+        return 0;
+    }
+    match chunk.get(from) {
+        BlockType::Lever
+        | BlockType::Button
+        | BlockType::PressurePlate
+        | BlockType::ItemDetector
+        | BlockType::Wire => power(from),
+        // gates only give power out of their front
+        BlockType::NotGate | BlockType::AndGate
+            if from + chunk.orientation(from).to_ivec3() == to =>
+        {
+            power(from)
+        }
+        _ => 0,
+    }
+}
+
+/// Whether anything next to `pos` could give it power when switched on
+fn wired(chunk: &VoxelChunk, pos: IVec3) -> bool {
+    neighbours(pos).any(|neighbour| output(chunk, &|_| MAX_POWER, neighbour, pos) > 0)
+}
+
+/// How many faces of `pos` other than `skip` are getting power
+fn powered_faces(
+    chunk: &VoxelChunk,
+    power: &impl Fn(IVec3) -> u8,
+    pos: IVec3,
+    skip: Option<IVec3>,
+) -> usize {
+    neighbours(pos)
+        .filter(|neighbour| Some(*neighbour) != skip)
+        .filter(|neighbour| output(chunk, power, *neighbour, pos) > 0)
+        .count()
+}
+
+fn use_blocks(
+    mut commands: Commands,
+    mut used: EventReader<UseBlock>,
+    selected: Res<HexSelect>,
+    mut chunks: ResMut<Assets<VoxelChunk>>,
+) {
+    if used.is_empty() {
+        return;
+    }
+    let Some(chunk) = chunks.get_mut(selected.chunk.id()) else {
+        used.clear();
+        return;
+    };
+    for UseBlock(pos) in used.read() {
+        match chunk.get(*pos) {
+            BlockType::Lever => {
+                let power = if chunk.power(*pos) > 0 { 0 } else { MAX_POWER };
+                chunk.set_power(*pos, power);
+            }
+            // turned off again by `update_signals` after one tick
+            BlockType::Button => chunk.set_power(*pos, MAX_POWER),
+            _ => continue,
+        }
+        commands.trigger(PlaySfx::Key(SfxKey::ButtonPress).at(pos.as_vec3()));
+    }
+}
+
+/// Pressure plates feel items or the player on top and item detectors look at the voxel in front
+fn sense_blocks(
+    selected: Res<HexSelect>,
+    mut chunks: ResMut<Assets<VoxelChunk>>,
+    items: Query<&GlobalTransform, With<Item>>,
+    player: Query<&GlobalTransform, With<VoxelPlayer>>,
+) {
+    let Some(chunk) = chunks.get(selected.chunk.id()) else {
+        return;
+    };
+    let items = items
+        .iter()
+        .map(|transform| transform.translation().round().as_ivec3())
+        .collect::<HashSet<_>>();
+    // the camera is about two blocks above the feet
+    let players = player
+        .iter()
+        .flat_map(|transform| {
+            let pos = transform.translation().round().as_ivec3();
+            [pos, pos - IVec3::Y, pos - IVec3::Y * 2]
+        })
+        .collect::<HashSet<_>>();
+    let mut flipped = Vec::new();
+    for pos in chunk_positions() {
+        let on = match chunk.get(pos) {
+            BlockType::PressurePlate => {
+                let above = pos + IVec3::Y;
+                items.contains(&above) || players.contains(&above)
+            }
+            BlockType::ItemDetector => items.contains(&(pos + chunk.orientation(pos).to_ivec3())),
+            _ => continue,
+        };
+        let power = if on { MAX_POWER } else { 0 };
+        if chunk.power(pos) != power {
+            flipped.push((pos, power));
+        }
+    }
+    set_powers(&mut chunks, selected.chunk.id(), flipped);
+}
+
+/// Writes `powers` into the chunk, leaving it untouched when there are none
+/// so its asset is only marked modified on ticks where a signal changed
+fn set_powers(chunks: &mut Assets<VoxelChunk>, id: AssetId<VoxelChunk>, powers: Vec<(IVec3, u8)>) {
+    if powers.is_empty() {
+        return;
+    }
+    let Some(chunk) = chunks.get_mut(id) else {
+        return;
+    };
+    for (pos, power) in powers {
+        chunk.set_power(pos, power);
+    }
+}
+
+fn update_signals(
+    mut commands: Commands,
+    selected: Res<HexSelect>,
+    mut chunks: ResMut<Assets<VoxelChunk>>,
+    machines: Query<
+        (Entity, &VoxelId, Has<Powered>),
+        Or<(With<Piston>, With<Extractor>, With<Melter>, With<Conveyor>)>,
+    >,
+) {
+    let Some(chunk) = chunks.get(selected.chunk.id()) else {
+        return;
+    };
+
+    // sources keep their power and gates switch on what their inputs had last tick
+    let mut next: HashMap<IVec3, u8> = HashMap::new();
+    let mut wires = Vec::new();
+    {
+        let last = |pos: IVec3| chunk.power(pos);
+        for pos in chunk_positions() {
+            let power = match chunk.get(pos) {
+                BlockType::Lever
+                | BlockType::Button
+                | BlockType::PressurePlate
+                | BlockType::ItemDetector => chunk.power(pos),
+                BlockType::NotGate => {
+                    let back = pos - chunk.orientation(pos).to_ivec3();
+                    if output(chunk, &last, back, pos) > 0 {
+                        0
+                    } else {
+                        MAX_POWER
+                    }
+                }
+                BlockType::AndGate => {
+                    let front = pos + chunk.orientation(pos).to_ivec3();
+                    if powered_faces(chunk, &last, pos, Some(front)) >= 2 {
+                        MAX_POWER
+                    } else {
+                        0
+                    }
+                }
+                BlockType::Wire => {
+                    wires.push(pos);
+                    0
+                }
+                _ => continue,
+            };
+            next.insert(pos, power);
+        }
+    }
+
+    // wires spread from the strongest power down, so each is only set once
+    let mut levels = vec![Vec::new(); MAX_POWER as usize + 1];
+    {
+        let now = |pos: IVec3| next.get(&pos).copied().unwrap_or_default();
+        for wire in wires {
+            let level = neighbours(wire)
+                .filter(|neighbour| chunk.get(*neighbour) != BlockType::Wire)
+                .map(|neighbour| output(chunk, &now, neighbour, wire))
+                .max()
+                .unwrap_or_default();
+            levels[level as usize].push(wire);
+        }
+    }
+    for level in (1..=MAX_POWER).rev() {
+        while let Some(wire) = levels[level as usize].pop() {
+            if next[&wire] >= level {
+                continue;
+            }
+            next.insert(wire, level);
+            for neighbour in neighbours(wire) {
+                if chunk.get(neighbour) == BlockType::Wire && next[&neighbour] < level - 1 {
+                    levels[level as usize - 1].push(neighbour);
+                }
+            }
+        }
+    }
+
+    let now = |pos: IVec3| next.get(&pos).copied().unwrap_or_default();
+    for (entity, id, powered) in &machines {
+        let on = !wired(chunk, id.0) || powered_faces(chunk, &now, id.0, None) > 0;
+        match (on, powered) {
+            (true, false) => {
+                commands.entity(entity).insert(Powered);
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Powered>();
+            }
+            _ => {}
+        }
+    }
+
+    // buttons only give a single tick of power
+    let changed = next
+        .into_iter()
+        .map(|(pos, power)| match chunk.get(pos) {
+            BlockType::Button => (pos, 0),
+            _ => (pos, power),
+        })
+        .filter(|(pos, power)| chunk.power(*pos) != *power)
+        .collect();
+    set_powers(&mut chunks, selected.chunk.id(), changed);
+}
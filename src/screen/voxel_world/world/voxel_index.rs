@@ -0,0 +1,86 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use super::VoxelId;
+
+pub(crate) fn voxel_index_plugin(app: &mut App) {
+    app.init_resource::<VoxelIndex>()
+        .observe(index_voxel)
+        .observe(unindex_voxel);
+}
+
+/// Resource to find the entity of the voxel at a position in the open chunk
+#[derive(Resource, Default)]
+pub struct VoxelIndex(HashMap<IVec3, Entity>);
+
+impl VoxelIndex {
+    pub fn get(&self, pos: IVec3) -> Option<Entity> {
+        self.0.get(&pos).copied()
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+fn index_voxel(
+    trigger: Trigger<OnAdd, VoxelId>,
+    ids: Query<&VoxelId>,
+    mut index: ResMut<VoxelIndex>,
+) {
+    let entity = trigger.entity();
+    if let Ok(id) = ids.get(entity) {
+        index.0.insert(id.0, entity);
+    }
+}
+
+fn unindex_voxel(
+    trigger: Trigger<OnRemove, VoxelId>,
+    ids: Query<&VoxelId>,
+    mut index: ResMut<VoxelIndex>,
+) {
+    let entity = trigger.entity();
+    let Ok(id) = ids.get(entity) else {
+        return;
+    };
+    // a new voxel may already be at this position
+    if index.get(id.0) == Some(entity) {
+        index.0.remove(&id.0);
+    }
+}
+
+#[test]
+fn voxel_index_tracks_voxels() {
+    let mut app = App::new();
+    voxel_index_plugin(&mut app);
+    let world = app.world_mut();
+
+    // placing
+    let placed = world.spawn(VoxelId(IVec3::new(1, 2, 3))).id();
+    assert_eq!(
+        world.resource::<VoxelIndex>().get(IVec3::new(1, 2, 3)),
+        Some(placed)
+    );
+    assert_eq!(world.resource::<VoxelIndex>().get(IVec3::ZERO), None);
+
+    // breaking
+    world.despawn(placed);
+    assert!(world.resource::<VoxelIndex>().is_empty());
+
+    // forming a multi block, the output can spawn before the old voxel is gone
+    let parts = (0..3)
+        .map(|y| world.spawn(VoxelId(IVec3::new(0, y, 0))).id())
+        .collect::<Vec<_>>();
+    let output = world.spawn(VoxelId(IVec3::new(0, 1, 0))).id();
+    for part in parts {
+        world.despawn(part);
+    }
+    let index = world.resource::<VoxelIndex>();
+    assert_eq!(index.len(), 1);
+    assert_eq!(index.get(IVec3::new(0, 1, 0)), Some(output));
+}
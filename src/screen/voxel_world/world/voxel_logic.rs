@@ -3,20 +3,29 @@ use bevy_rapier3d::{
     plugin::RapierContext,
     prelude::{Collider, ExternalImpulse, QueryFilter, ShapeCastOptions},
 };
+use rand::Rng;
 
 use crate::{
-    game::{assets::SfxKey, audio::sfx::PlaySfx, HexSelect},
+    game::{assets::SfxKey, audio::sfx::PlaySfx, main_character::Player, HexSelect},
     screen::{
+        inventory::Inventory,
+        progression::{Delivery, DeliveryGoals, Goals},
+        quests::QuestEvent,
         voxel_world::{
-            item::{spawn_item, Item},
+            item::{spawn_item, DropItems, Item},
             voxel_util::VoxelPlayer,
             voxels::{Block, BlockType, Blocks},
         },
-        NextTarget, Score, Screen, Target,
+        Score, Screen, Target,
     },
 };
 
-use super::{VoxelChunk, VoxelId};
+use super::{
+    energy::{EnergyNetworks, EnergySet, CONVEYOR_COST, DRILL_COST, PISTON_COST},
+    fluid::{pour_beside, Fluid, FULL},
+    signal::{Powered, SignalUpdate},
+    VoxelChunk, VoxelId,
+};
 
 pub struct VoxelLogic;
 
@@ -32,11 +41,57 @@ impl Plugin for VoxelLogic {
                 conveyor_init,
                 conveyor_logic,
             )
+                .after(SignalUpdate)
+                .after(EnergySet::Supply)
+                .before(EnergySet::Store)
                 .run_if(in_state(Screen::VoxelWorld)),
+        )
+        .add_systems(
+            FixedUpdate,
+            ambient_sounds.run_if(in_state(Screen::VoxelWorld)),
         );
     }
 }
 
+/// Sounds a block plays now and then from where it is
+#[derive(Component)]
+pub struct AmbientSound {
+    sounds: Vec<SfxKey>,
+    /// a random wait so a row of machines does not play in step
+    timer: Timer,
+}
+
+/// Seconds between ambient sounds of one block
+const AMBIENT_DELAY: std::ops::Range<f32> = 3.0..8.0;
+
+impl AmbientSound {
+    pub fn new(sounds: Vec<SfxKey>) -> AmbientSound {
+        AmbientSound {
+            sounds,
+            timer: ambient_timer(&mut rand::thread_rng()),
+        }
+    }
+}
+
+fn ambient_timer(rng: &mut impl Rng) -> Timer {
+    Timer::from_seconds(rng.gen_range(AMBIENT_DELAY), TimerMode::Once)
+}
+
+fn ambient_sounds(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut blocks: Query<(&VoxelId, &mut AmbientSound)>,
+) {
+    let mut rng = rand::thread_rng();
+    for (id, mut sound) in &mut blocks {
+        if !sound.timer.tick(time.delta()).finished() {
+            continue;
+        }
+        sound.timer = ambient_timer(&mut rng);
+        commands.trigger(PlaySfx::OneOf(sound.sounds.clone()).at(id.0.as_vec3()));
+    }
+}
+
 #[derive(Component)]
 pub struct Extractor;
 
@@ -45,11 +100,12 @@ pub struct Melter;
 
 fn drill_logic(
     selected: Res<HexSelect>,
-    extractors: Query<(&VoxelId, &Transform), With<Extractor>>,
+    extractors: Query<(&VoxelId, &Transform), (With<Extractor>, With<Powered>)>,
     mut commands: Commands,
     voxels: Res<Blocks>,
     data: Res<Assets<Block>>,
     chunks: Res<Assets<VoxelChunk>>,
+    mut energy: ResMut<EnergyNetworks>,
 ) {
     let Some(chunk) = chunks.get(selected.chunk.id()) else {
         return;
@@ -61,6 +117,9 @@ fn drill_logic(
         if !block.can_mine() {
             return;
         }
+        if !energy.draw(extractor.0, DRILL_COST) {
+            continue;
+        }
         spawn_item(
             below,
             &data,
@@ -71,15 +130,18 @@ fn drill_logic(
     }
 }
 
+/// Melters turn ore on top into metal, poured into a container beside them when it has room
 fn melter_logic(
     context: Res<RapierContext>,
-    melters: Query<&Transform, With<Melter>>,
+    melters: Query<(&VoxelId, &Transform), (With<Melter>, With<Powered>)>,
     mut items: Query<&mut BlockType, With<Item>>,
     mut commands: Commands,
     data: Res<Assets<Block>>,
     voxels: Res<Blocks>,
+    selected: Res<HexSelect>,
+    mut chunks: ResMut<Assets<VoxelChunk>>,
 ) {
-    for pos in &melters {
+    for (id, pos) in &melters {
         let Some((up, _)) = context.cast_shape(
             pos.translation,
             Quat::IDENTITY,
@@ -112,6 +174,7 @@ fn melter_logic(
             continue;
         };
 
+        let up_entity = up;
         let Ok([mut up, fule]) = items.get_many_mut([up, down]) else {
             continue;
         };
@@ -123,9 +186,18 @@ fn melter_logic(
         let melt = voxels.get(up.clone());
         let melt = data.get(melt.id()).expect("All Blocks loaded");
         if let Some(melt) = melt.melt() {
-            *up = melt;
+            let poured = Fluid::molten(&melt).is_some_and(|fluid| {
+                chunks
+                    .get_mut(selected.chunk.id())
+                    .is_some_and(|chunk| pour_beside(chunk, id.0, fluid, FULL))
+            });
+            if poured {
+                commands.entity(up_entity).despawn();
+            } else {
+                *up = melt;
+            }
             commands.entity(down).despawn();
-            commands.trigger(PlaySfx::Key(SfxKey::Melt));
+            commands.trigger(PlaySfx::Key(SfxKey::Melt).at(pos.translation));
         }
     }
 }
@@ -139,9 +211,17 @@ fn score_logic(
     items: Query<&BlockType, With<Item>>,
     mut commands: Commands,
     mut target: ResMut<Target>,
-    mut next_target: ResMut<NextTarget>,
     mut score: ResMut<Score>,
+    goals: Res<Goals>,
+    goal_data: Res<Assets<DeliveryGoals>>,
+    mut player: Query<&mut Inventory, With<Player>>,
+    blocks: Res<Blocks>,
+    block_data: Res<Assets<Block>>,
+    mut spilled: EventWriter<DropItems>,
 ) {
+    let Some(goals) = goal_data.get(goals.0.id()) else {
+        return;
+    };
     for pos in &score_giver {
         let Some((up, _)) = context.cast_shape(
             pos.translation,
@@ -163,12 +243,31 @@ fn score_logic(
             return;
         };
 
-        if block == &target.0 {
-            commands.trigger(PlaySfx::Key(SfxKey::Progress));
-            target.0 = next_target.next();
-            score.0 += 1;
-        } else {
-            commands.trigger(PlaySfx::Key(SfxKey::NoProgress));
+        // quests count everything fed in, whether the current goal wants it or not
+        commands.trigger(QuestEvent::Delivered(block.clone()));
+        match target.deliver(block, goals) {
+            Delivery::Rejected => {
+                commands.trigger(PlaySfx::Key(SfxKey::NoProgress).at(pos.translation))
+            }
+            Delivery::Counted => {
+                commands.trigger(PlaySfx::Key(SfxKey::Progress).at(pos.translation))
+            }
+            Delivery::GoalMet(goal) => {
+                commands.trigger(PlaySfx::Key(SfxKey::Progress).at(pos.translation));
+                score.0 += goal.score;
+                for mut inventory in &mut player {
+                    for (reward, count) in goal.rewards.iter() {
+                        let max_stack = blocks.max_stack(&block_data, reward);
+                        let overflow = inventory.add_resource(reward.clone(), *count, max_stack);
+                        if overflow > 0 {
+                            spilled.send(DropItems {
+                                block_type: reward.clone(),
+                                count: overflow,
+                            });
+                        }
+                    }
+                }
+            }
         }
         commands.entity(up).despawn();
     }
@@ -178,13 +277,14 @@ fn score_logic(
 pub struct Piston(pub f32);
 
 fn piston_logic(
-    pistons: Query<(&Transform, &Piston)>,
+    pistons: Query<(&VoxelId, &Transform, &Piston), With<Powered>>,
     context: Res<RapierContext>,
     items: Query<Entity, With<Item>>,
     player: Query<&Parent, With<VoxelPlayer>>,
     mut commands: Commands,
+    mut energy: ResMut<EnergyNetworks>,
 ) {
-    for (pos, power) in &pistons {
+    for (id, pos, power) in &pistons {
         let Some((hit, _)) = context.cast_shape(
             pos.translation,
             Quat::IDENTITY,
@@ -200,6 +300,13 @@ fn piston_logic(
         ) else {
             continue;
         };
+        let pushes_player = player.iter().any(|player| player.get() == hit);
+        if !pushes_player && !items.contains(hit) {
+            continue;
+        }
+        if !energy.draw(id.0, PISTON_COST) {
+            continue;
+        }
 
         for player in &player {
             if hit == player.get() {
@@ -219,16 +326,37 @@ fn piston_logic(
     }
 }
 
+/// Conveyors pay for a second of running at a time
 #[derive(Component)]
-pub struct Conveyor;
+pub struct Conveyor {
+    /// what is left of the second last paid for
+    paid: Timer,
+}
+
+impl Default for Conveyor {
+    fn default() -> Self {
+        // starts spent so the first tick it runs pays
+        let mut paid = Timer::from_seconds(1., TimerMode::Once);
+        paid.tick(paid.duration());
+        Conveyor { paid }
+    }
+}
 
 fn conveyor_logic(
-    conveyors: Query<&Children, With<Conveyor>>,
+    mut conveyors: Query<(&VoxelId, &Children, &mut Conveyor), With<Powered>>,
     mut transforms: Query<&mut Transform, With<Collider>>,
     time: Res<Time>,
+    mut energy: ResMut<EnergyNetworks>,
 ) {
-    for conveyor in &conveyors {
-        for child in conveyor.iter() {
+    for (id, children, mut conveyor) in &mut conveyors {
+        if conveyor.paid.finished() {
+            if !energy.draw(id.0, CONVEYOR_COST) {
+                continue;
+            }
+            conveyor.paid.reset();
+        }
+        conveyor.paid.tick(time.delta());
+        for child in children.iter() {
             if let Ok(mut transform) = transforms.get_mut(*child) {
                 transform.translation.z += time.delta_seconds();
                 if transform.translation.z > 0.375 {
@@ -2,8 +2,12 @@ use bevy::{
     log::warn,
     prelude::{GamepadAxisType, GamepadButtonType, KeyCode, MouseButton},
 };
-use leafwing_input_manager::{axislike::AxisType, prelude::DualAxis};
+use leafwing_input_manager::{
+    axislike::AxisType,
+    prelude::{DualAxis, InputKind, Modifier},
+};
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum KeyIcons {
     Keyboard(KeyCode),
     GamepadButton(GamepadButtonType),
@@ -19,13 +23,24 @@ pub enum KeyIcons {
     NotDone,
     Add,
     NotSupported,
+    /// Inputs that are held together
+    Chord(Vec<KeyIcons>),
+    /// Inputs for each direction of a virtual stick or axis
+    Directions(Vec<KeyIcons>),
 }
 
 const NOT_DONE_INDEX: usize = 17 * 34;
 
 impl super::widgets::UiIcon for KeyIcons {
     fn index(&self) -> usize {
-        match self {
+        self.icon().unwrap_or(NOT_DONE_INDEX)
+    }
+}
+
+impl KeyIcons {
+    /// Where the icon is in the atlas, `None` when the atlas has no icon and [`KeyIcons::label`] is shown instead
+    pub fn icon(&self) -> Option<usize> {
+        let index = match self {
             KeyIcons::GamepadButton(buttons) => match buttons {
                 GamepadButtonType::South => 8,
                 GamepadButtonType::East => 9,
@@ -41,13 +56,13 @@ impl super::widgets::UiIcon for KeyIcons {
                 GamepadButtonType::DPadDown => 34 + 3,
                 GamepadButtonType::DPadLeft => 34 + 4,
                 GamepadButtonType::DPadRight => 34 + 2,
-                _ => NOT_DONE_INDEX,
+                _ => return None,
             },
             KeyIcons::MouseButton(button) => match button {
                 MouseButton::Left => 2 * 34 + 9,
                 MouseButton::Right => 2 * 34 + 10,
                 MouseButton::Middle => 2 * 34 + 11,
-                _ => NOT_DONE_INDEX,
+                _ => return None,
             },
             // KeyIcons::Keyboard(_) => todo!(),
             KeyIcons::GamepadAxis(axis) => match axis {
@@ -55,7 +70,7 @@ impl super::widgets::UiIcon for KeyIcons {
                 GamepadAxisType::LeftStickY => 12 * 34 + 14,
                 GamepadAxisType::RightStickX => 14 * 34 + 13,
                 GamepadAxisType::RightStickY => 14 * 34 + 14,
-                _ => NOT_DONE_INDEX,
+                _ => return None,
             },
             KeyIcons::Keyboard(key) => match key {
                 KeyCode::Escape => 17,
@@ -127,7 +142,7 @@ impl super::widgets::UiIcon for KeyIcons {
                 KeyCode::ArrowDown => 4 * 34 + 32,
                 KeyCode::ArrowLeft => 4 * 34 + 33,
                 KeyCode::Enter => 3 * 34 + 32,
-                _ => NOT_DONE_INDEX,
+                _ => return None,
             },
             KeyIcons::LeftStick => 12 * 34 + 15,
             KeyIcons::RightStick => 14 * 34 + 15,
@@ -139,8 +154,113 @@ impl super::widgets::UiIcon for KeyIcons {
             KeyIcons::NotSupported => 12 * 34 + 28,
             KeyIcons::NotDone => NOT_DONE_INDEX,
             KeyIcons::Add => 20 * 34 + 14,
+            KeyIcons::Chord(_) | KeyIcons::Directions(_) => return None,
+        };
+        Some(index)
+    }
+
+    /// A short name for the input, shown when there is no icon and in written warnings
+    pub fn label(&self) -> String {
+        match self {
+            KeyIcons::Keyboard(key) => key_label(key),
+            KeyIcons::GamepadButton(button) => match button {
+                GamepadButtonType::South => "A".to_string(),
+                GamepadButtonType::East => "B".to_string(),
+                GamepadButtonType::North => "Y".to_string(),
+                GamepadButtonType::West => "X".to_string(),
+                GamepadButtonType::LeftTrigger => "LB".to_string(),
+                GamepadButtonType::LeftTrigger2 => "LT".to_string(),
+                GamepadButtonType::RightTrigger => "RB".to_string(),
+                GamepadButtonType::RightTrigger2 => "RT".to_string(),
+                GamepadButtonType::LeftThumb => "LS".to_string(),
+                GamepadButtonType::RightThumb => "RS".to_string(),
+                GamepadButtonType::Mode => "Home".to_string(),
+                GamepadButtonType::Other(other) => format!("Pad {other}"),
+                other => format!("{other:?}").replace("DPad", "D-"),
+            },
+            KeyIcons::GamepadAxis(axis) => match axis {
+                GamepadAxisType::LeftStickX => "LS X".to_string(),
+                GamepadAxisType::LeftStickY => "LS Y".to_string(),
+                GamepadAxisType::RightStickX => "RS X".to_string(),
+                GamepadAxisType::RightStickY => "RS Y".to_string(),
+                GamepadAxisType::LeftZ => "LT Axis".to_string(),
+                GamepadAxisType::RightZ => "RT Axis".to_string(),
+                GamepadAxisType::Other(other) => format!("Axis {other}"),
+            },
+            KeyIcons::MouseButton(button) => match button {
+                MouseButton::Left => "LMB".to_string(),
+                MouseButton::Right => "RMB".to_string(),
+                MouseButton::Middle => "MMB".to_string(),
+                MouseButton::Back => "Mouse 4".to_string(),
+                MouseButton::Forward => "Mouse 5".to_string(),
+                MouseButton::Other(other) => format!("Mouse {other}"),
+            },
+            KeyIcons::MouseMotion => "Mouse".to_string(),
+            KeyIcons::MouseScroll => "Scroll".to_string(),
+            KeyIcons::MouseScrollUp => "Scroll Up".to_string(),
+            KeyIcons::MouseScrollDown => "Scroll Down".to_string(),
+            KeyIcons::LeftStick => "LS".to_string(),
+            KeyIcons::RightStick => "RS".to_string(),
+            KeyIcons::UntypedStick => "Stick".to_string(),
+            KeyIcons::NotDone => "?".to_string(),
+            KeyIcons::Add => "+".to_string(),
+            KeyIcons::NotSupported => "Unsupported".to_string(),
+            KeyIcons::Chord(keys) => join_labels(keys, " + "),
+            KeyIcons::Directions(keys) => join_labels(keys, "/"),
+        }
+    }
+}
+
+fn join_labels(keys: &[KeyIcons], separator: &str) -> String {
+    keys.iter()
+        .map(KeyIcons::label)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// The key's name shortened to fit next to the icons, `ShiftLeft` becomes `LShift`
+fn key_label(key: &KeyCode) -> String {
+    if let KeyCode::Unidentified(_) = key {
+        return "Unknown".to_string();
+    }
+    let name = format!("{key:?}");
+    if let Some(letter) = name.strip_prefix("Key").filter(|rest| rest.len() == 1) {
+        return letter.to_string();
+    }
+    if let Some(digit) = name.strip_prefix("Digit") {
+        return digit.to_string();
+    }
+    let name = name
+        .replace("Numpad", "Num")
+        .replace("Control", "Ctrl")
+        .replace("Arrow", "")
+        .replace("Audio", "")
+        .replace("Launch", "")
+        .replace("Browser", "Web")
+        .replace("Media", "");
+    for (side, short) in [("Left", "L"), ("Right", "R")] {
+        if let Some(key) = name.strip_suffix(side).filter(|key| !key.is_empty()) {
+            return format!("{short}{key}");
         }
     }
+    name
+}
+
+impl From<Modifier> for KeyIcons {
+    fn from(value: Modifier) -> Self {
+        KeyIcons::Keyboard(match value {
+            Modifier::Alt => KeyCode::AltLeft,
+            Modifier::Control => KeyCode::ControlLeft,
+            Modifier::Shift => KeyCode::ShiftLeft,
+            Modifier::Super => KeyCode::SuperLeft,
+        })
+    }
+}
+
+impl From<InputKind> for KeyIcons {
+    fn from(value: InputKind) -> Self {
+        KeyIcons::from(leafwing_input_manager::prelude::UserInput::Single(value))
+    }
 }
 
 impl From<leafwing_input_manager::user_input::UserInput> for KeyIcons {
@@ -150,7 +270,13 @@ impl From<leafwing_input_manager::user_input::UserInput> for KeyIcons {
                 leafwing_input_manager::prelude::InputKind::GamepadButton(button) => {
                     KeyIcons::GamepadButton(button)
                 }
-                leafwing_input_manager::prelude::InputKind::SingleAxis(_) => KeyIcons::NotDone,
+                leafwing_input_manager::prelude::InputKind::SingleAxis(axis) => {
+                    match axis.axis_type {
+                        AxisType::Gamepad(axis) => KeyIcons::GamepadAxis(axis),
+                        AxisType::MouseWheel(_) => KeyIcons::MouseScroll,
+                        AxisType::MouseMotion(_) => KeyIcons::MouseMotion,
+                    }
+                }
                 leafwing_input_manager::prelude::InputKind::DualAxis(DualAxis {
                     x,
                     y,
@@ -175,7 +301,9 @@ impl From<leafwing_input_manager::user_input::UserInput> for KeyIcons {
                 leafwing_input_manager::prelude::InputKind::PhysicalKey(key) => {
                     KeyIcons::Keyboard(key)
                 }
-                leafwing_input_manager::prelude::InputKind::Modifier(_) => KeyIcons::NotDone,
+                leafwing_input_manager::prelude::InputKind::Modifier(modifier) => {
+                    KeyIcons::from(modifier)
+                }
                 leafwing_input_manager::prelude::InputKind::Mouse(button) => {
                     KeyIcons::MouseButton(button)
                 }
@@ -187,15 +315,71 @@ impl From<leafwing_input_manager::user_input::UserInput> for KeyIcons {
                         leafwing_input_manager::prelude::MouseWheelDirection::Down => {
                             KeyIcons::MouseScrollDown
                         }
-                        _ => KeyIcons::NotDone,
+                        // sideways scrolls use the plain wheel
+                        _ => KeyIcons::MouseScroll,
                     }
                 }
-                leafwing_input_manager::prelude::InputKind::MouseMotion(_) => KeyIcons::NotDone,
+                leafwing_input_manager::prelude::InputKind::MouseMotion(_) => KeyIcons::MouseMotion,
                 _ => KeyIcons::NotDone,
             },
-            leafwing_input_manager::prelude::UserInput::Chord(_) => KeyIcons::NotDone,
-            leafwing_input_manager::prelude::UserInput::VirtualDPad(_) => KeyIcons::NotDone,
-            leafwing_input_manager::prelude::UserInput::VirtualAxis(_) => KeyIcons::NotDone,
+            leafwing_input_manager::prelude::UserInput::Chord(keys) => {
+                KeyIcons::Chord(keys.into_iter().map(KeyIcons::from).collect())
+            }
+            leafwing_input_manager::prelude::UserInput::VirtualDPad(dpad) => KeyIcons::Directions(
+                [dpad.up, dpad.left, dpad.down, dpad.right]
+                    .into_iter()
+                    .map(KeyIcons::from)
+                    .collect(),
+            ),
+            leafwing_input_manager::prelude::UserInput::VirtualAxis(axis) => KeyIcons::Directions(
+                [axis.negative, axis.positive]
+                    .into_iter()
+                    .map(KeyIcons::from)
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[test]
+fn every_key_has_an_icon_or_a_label() {
+    use bevy::input::keyboard::NativeKeyCode;
+    use bevy::reflect::{DynamicEnum, DynamicVariant, FromReflect, TypeInfo, Typed, VariantInfo};
+
+    let TypeInfo::Enum(info) = KeyCode::type_info() else {
+        panic!("KeyCode should reflect as an enum");
+    };
+    let mut keys = vec![KeyCode::Unidentified(NativeKeyCode::Unidentified)];
+    for variant in info.iter() {
+        if let VariantInfo::Unit(unit) = variant {
+            let key = KeyCode::from_reflect(&DynamicEnum::new(unit.name(), DynamicVariant::Unit))
+                .expect("unit variants build from reflection");
+            keys.push(key);
         }
     }
+    assert!(keys.len() > 100, "only found {} keys", keys.len());
+    for key in keys {
+        let icon = KeyIcons::Keyboard(key);
+        let label = icon.label();
+        assert!(
+            icon.icon().is_some() || !label.is_empty(),
+            "{key:?} has no icon or label"
+        );
+        assert!(
+            !label.contains('('),
+            "{key:?} label {label} is not readable"
+        );
+    }
+}
+
+#[test]
+fn chords_label_every_key() {
+    use leafwing_input_manager::prelude::UserInput;
+
+    let chord = KeyIcons::from(UserInput::Chord(vec![
+        InputKind::Modifier(Modifier::Shift),
+        InputKind::PhysicalKey(KeyCode::Digit1),
+    ]));
+    assert_eq!(chord.icon(), None);
+    assert_eq!(chord.label(), "LShift + 1");
 }
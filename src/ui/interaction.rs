@@ -17,18 +17,25 @@ pub struct InteractionPalette {
     pub none: Color,
     pub hovered: Color,
     pub pressed: Color,
+    /// Outline shown while gamepad or keyboard navigation has the focus on it
+    pub focused: Color,
 }
 
-fn apply_interaction_palette(
+impl InteractionPalette {
+    pub fn color(&self, interaction: Interaction) -> Color {
+        match interaction {
+            Interaction::None => self.none,
+            Interaction::Hovered => self.hovered,
+            Interaction::Pressed => self.pressed,
+        }
+    }
+}
+
+pub fn apply_interaction_palette(
     mut palette_query: InteractionQuery<(&InteractionPalette, &mut BackgroundColor)>,
 ) {
     for (interaction, (palette, mut background)) in &mut palette_query {
-        *background = match interaction {
-            Interaction::None => palette.none,
-            Interaction::Hovered => palette.hovered,
-            Interaction::Pressed => palette.pressed,
-        }
-        .into();
+        *background = palette.color(*interaction).into();
     }
 }
 
@@ -5,12 +5,14 @@
 
 pub mod icons;
 pub mod interaction;
+pub mod navigation;
 pub mod palette;
 pub mod widgets;
 
 pub mod prelude {
     pub use super::{
         interaction::{InteractionPalette, InteractionQuery},
+        navigation::BackButton,
         palette as ui_palette,
         widgets::{Containers as _, Widgets as _},
     };
@@ -19,5 +21,5 @@ pub mod prelude {
 use bevy::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins(interaction::plugin);
+    app.add_plugins((interaction::plugin, navigation::plugin));
 }
@@ -0,0 +1,293 @@
+//! Moving a focus between buttons with a gamepad or the arrow keys.
+//!
+//! Every node with an [`InteractionPalette`] can take the focus. A direction moves it to the
+//! nearest visible node that way, confirm presses it like a click and back presses the
+//! [`BackButton`] of the open menu.
+
+use bevy::{prelude::*, ui::UiSystem};
+use leafwing_input_manager::{
+    plugin::InputManagerSystem,
+    prelude::{ActionState, DualAxis, InputKind, InputManagerPlugin, InputMap, UserInput},
+    Actionlike,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{assets::SfxKey, audio::sfx::PlaySfx};
+
+use super::interaction::InteractionPalette;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(InputManagerPlugin::<UiAction>::default())
+        .init_resource::<ActionState<UiAction>>()
+        .insert_resource(default_ui_inputs())
+        .init_resource::<UiFocus>()
+        .add_systems(
+            PreUpdate,
+            navigate_focus
+                .after(UiSystem::Focus)
+                .after(InputManagerSystem::Update),
+        )
+        .add_systems(Update, show_focus_ring);
+}
+
+/// Inputs for moving around menus, kept apart from [`crate::game::PlayerAction`] so they can
+/// share buttons with gameplay
+#[derive(Debug, Actionlike, Reflect, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UiAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// a stick, moving the focus once each time it is pushed over
+    Navigate,
+    Confirm,
+    Back,
+    /// halves the focused inventory slot
+    Split,
+    /// moves the focused inventory slot between the hotbar and the rest
+    QuickMove,
+    /// throws an item from the focused inventory slot into the world
+    Drop,
+}
+
+fn default_ui_inputs() -> InputMap<UiAction> {
+    let mut map = InputMap::default();
+    for (action, button, key) in [
+        (UiAction::Up, GamepadButtonType::DPadUp, KeyCode::ArrowUp),
+        (
+            UiAction::Down,
+            GamepadButtonType::DPadDown,
+            KeyCode::ArrowDown,
+        ),
+        (
+            UiAction::Left,
+            GamepadButtonType::DPadLeft,
+            KeyCode::ArrowLeft,
+        ),
+        (
+            UiAction::Right,
+            GamepadButtonType::DPadRight,
+            KeyCode::ArrowRight,
+        ),
+        (UiAction::Confirm, GamepadButtonType::South, KeyCode::Enter),
+        (UiAction::Back, GamepadButtonType::East, KeyCode::Backspace),
+    ] {
+        map.insert(action, UserInput::Single(InputKind::GamepadButton(button)));
+        map.insert(action, UserInput::Single(InputKind::PhysicalKey(key)));
+    }
+    map.insert(
+        UiAction::Split,
+        UserInput::Single(InputKind::GamepadButton(GamepadButtonType::West)),
+    );
+    map.insert(
+        UiAction::Split,
+        UserInput::Single(InputKind::Mouse(MouseButton::Right)),
+    );
+    map.insert(
+        UiAction::QuickMove,
+        UserInput::Single(InputKind::GamepadButton(GamepadButtonType::North)),
+    );
+    map.insert(
+        UiAction::Drop,
+        UserInput::Single(InputKind::GamepadButton(GamepadButtonType::LeftThumb)),
+    );
+    map.insert(
+        UiAction::Drop,
+        UserInput::Single(InputKind::PhysicalKey(KeyCode::KeyX)),
+    );
+    map.insert(
+        UiAction::Navigate,
+        UserInput::Single(InputKind::DualAxis(DualAxis::left_stick())),
+    );
+    map
+}
+
+/// Resource for the node that confirm will press
+#[derive(Resource, Debug, Default)]
+pub struct UiFocus {
+    pub entity: Option<Entity>,
+    /// set while a menu listens for any input, like when rebinding a key
+    pub paused: bool,
+    /// pressed by confirm on the last frame and released on this one
+    pressed: Option<Entity>,
+}
+
+/// Marker for the button that closes its menu, pressed by [`UiAction::Back`]
+#[derive(Component)]
+pub struct BackButton;
+
+/// How far the stick has to be pushed to move the focus
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// The closest of `candidates` from `from` in `direction`, favouring ones that are in line with it
+fn nearest_in_direction(
+    from: Vec2,
+    direction: Vec2,
+    candidates: impl Iterator<Item = (Entity, Vec2)>,
+) -> Option<Entity> {
+    candidates
+        .filter_map(|(entity, to)| {
+            let offset = to - from;
+            let along = offset.dot(direction);
+            // nodes in the same row or column sit within a pixel of each other
+            (along > 1.).then(|| (entity, along + 2. * offset.perp_dot(direction).abs()))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity)
+}
+
+fn navigate_focus(
+    mut commands: Commands,
+    input: Res<ActionState<UiAction>>,
+    mut focus: ResMut<UiFocus>,
+    mut stick_held: Local<bool>,
+    mut focusable: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &InheritedVisibility,
+            &mut Interaction,
+            Has<BackButton>,
+        ),
+        With<InteractionPalette>,
+    >,
+) {
+    if let Some(pressed) = focus.pressed.take() {
+        if let Ok((_, _, _, mut interaction, _)) = focusable.get_mut(pressed) {
+            if *interaction == Interaction::Pressed {
+                *interaction = Interaction::None;
+            }
+        }
+    }
+    if focus.paused {
+        return;
+    }
+    // the focus is dropped when its menu closes or hides
+    if let Some(entity) = focus.entity {
+        if !focusable
+            .get(entity)
+            .is_ok_and(|(_, _, visible, _, _)| visible.get())
+        {
+            focus.entity = None;
+        }
+    }
+    // the mouse takes the focus to whatever it moves over
+    for (entity, _, _, interaction, _) in &mut focusable {
+        if interaction.is_changed() && *interaction == Interaction::Hovered {
+            focus.entity = Some(entity);
+        }
+    }
+
+    let mut direction = [
+        (UiAction::Up, Vec2::NEG_Y),
+        (UiAction::Down, Vec2::Y),
+        (UiAction::Left, Vec2::NEG_X),
+        (UiAction::Right, Vec2::X),
+    ]
+    .into_iter()
+    .find(|(action, _)| input.just_pressed(action))
+    .map(|(_, direction)| direction);
+    let stick = input
+        .axis_pair(&UiAction::Navigate)
+        .map(|axis| axis.xy())
+        .unwrap_or_default();
+    let pushed = stick.length() > STICK_THRESHOLD;
+    if pushed && !*stick_held && direction.is_none() {
+        // ui positions go down the screen while the stick goes up
+        direction = Some(if stick.x.abs() > stick.y.abs() {
+            Vec2::new(stick.x.signum(), 0.)
+        } else {
+            Vec2::new(0., -stick.y.signum())
+        });
+    }
+    *stick_held = pushed;
+
+    if let Some(direction) = direction {
+        let candidates = focusable
+            .iter()
+            .filter(|(_, _, visible, _, _)| visible.get())
+            .map(|(entity, transform, ..)| (entity, transform.translation().truncate()));
+        let current = focus
+            .entity
+            .and_then(|entity| focusable.get(entity).ok())
+            .map(|(_, transform, ..)| transform.translation().truncate());
+        let next = match current {
+            Some(from) => nearest_in_direction(from, direction, candidates),
+            // nothing has the focus yet, so start from the top left
+            None => candidates
+                .min_by(|a, b| a.1.y.total_cmp(&b.1.y).then(a.1.x.total_cmp(&b.1.x)))
+                .map(|(entity, _)| entity),
+        };
+        if next.is_some() && next != focus.entity {
+            focus.entity = next;
+            commands.trigger(PlaySfx::Key(SfxKey::ButtonHover));
+        }
+    }
+
+    let target = if input.just_pressed(&UiAction::Confirm) {
+        focus.entity
+    } else if input.just_pressed(&UiAction::Back) {
+        focusable
+            .iter()
+            .find(|(_, _, visible, _, back)| *back && visible.get())
+            .map(|(entity, ..)| entity)
+    } else {
+        None
+    };
+    // pressed like a click so every menu's `InteractionQuery` handles it
+    if let Some(target) = target {
+        if let Ok((_, _, _, mut interaction, _)) = focusable.get_mut(target) {
+            *interaction = Interaction::Pressed;
+            focus.pressed = Some(target);
+        }
+    }
+}
+
+/// Outlines the focused node in its palette's focus colour
+fn show_focus_ring(
+    mut commands: Commands,
+    focus: Res<UiFocus>,
+    mut last: Local<Option<Entity>>,
+    palettes: Query<&InteractionPalette>,
+) {
+    if *last == focus.entity {
+        return;
+    }
+    if let Some(entity) = last.take() {
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.remove::<Outline>();
+        }
+    }
+    if let Some(entity) = focus.entity {
+        if let Ok(palette) = palettes.get(entity) {
+            commands
+                .entity(entity)
+                .insert(Outline::new(Val::Px(3.), Val::Px(2.), palette.focused));
+        }
+    }
+    *last = focus.entity;
+}
+
+#[test]
+fn focus_moves_to_the_nearest_node_in_line() {
+    let [left, right, below, far_right] = [0, 1, 2, 3].map(Entity::from_raw);
+    let nodes = [
+        (left, Vec2::new(0., 0.)),
+        (right, Vec2::new(100., 0.)),
+        (below, Vec2::new(0., 80.)),
+        (far_right, Vec2::new(300., 10.)),
+    ];
+    let from = Vec2::ZERO;
+    assert_eq!(
+        nearest_in_direction(from, Vec2::X, nodes.into_iter()),
+        Some(right)
+    );
+    assert_eq!(
+        nearest_in_direction(from, Vec2::Y, nodes.into_iter()),
+        Some(below)
+    );
+    assert_eq!(
+        nearest_in_direction(from, Vec2::NEG_X, nodes.into_iter()),
+        None
+    );
+}
@@ -11,8 +11,11 @@ use super::icons::KeyIcons;
 use super::{interaction::InteractionPalette, palette::*};
 use crate::game::PlayerAction;
 use crate::screen::inventory::{Inventory, InventorySlot};
-use crate::screen::voxel_world::ui::FullInventoryUI;
-use crate::screen::voxel_world::voxels::{Block, Blocks};
+use crate::screen::voxel_world::block_icons::BlockIcons;
+use crate::screen::voxel_world::ui::{
+    FullInventoryUI, HotbarSlot, HotbarUI, InventorySlotButton, SlotContents, SlotName,
+    SlotQuantity, SortInventoryButton,
+};
 
 // Define the UiRoot component
 #[derive(Component)]
@@ -28,34 +31,31 @@ pub trait Widgets {
 
     /// Spawn a simple text label.
     fn label(&mut self, text: impl Into<String>) -> EntityCommands;
-    /// Spawn an inventory slot UI element
+    /// Spawn an inventory slot UI element showing the slot at `index`
     fn inventory_slot(
         &mut self,
+        index: usize,
         slot: &InventorySlot,
-        voxel_data: &Assets<Block>,
-        voxels: &Blocks,
-        materials: &Assets<StandardMaterial>,
+        icons: &BlockIcons,
     ) -> EntityCommands;
 
     fn horizontal(&mut self) -> EntityCommands;
 
-    /// Spawn a hotbar inventory UI
-    fn hotbar(
+    /// Spawn a row with a label between buttons that lower and raise a setting.
+    /// `label` is added to the label so its text can be updated.
+    fn stepper(
         &mut self,
-        inventory: &Inventory,
-        voxels: &Blocks,
-        voxel_data: &Assets<Block>,
-        materials: &Assets<StandardMaterial>,
+        text: impl Into<String>,
+        lower: impl Bundle,
+        raise: impl Bundle,
+        label: impl Bundle,
     ) -> EntityCommands;
 
+    /// Spawn a hotbar inventory UI
+    fn hotbar(&mut self, inventory: &Inventory, icons: &BlockIcons) -> EntityCommands;
+
     /// Spawn a complete inventory UI
-    fn full_inventory(
-        &mut self,
-        inventory: &Inventory,
-        voxels: &Blocks,
-        voxel_data: &Assets<Block>,
-        materials: &Assets<StandardMaterial>,
-    ) -> EntityCommands;
+    fn full_inventory(&mut self, inventory: &Inventory, icons: &BlockIcons) -> EntityCommands;
 
     fn key_bindings(
         &mut self,
@@ -77,6 +77,22 @@ pub trait Widgets {
         icons: Handle<Image>,
         icon: impl UiIcon,
     ) -> EntityCommands;
+
+    /// Spawn the icon for an input, or its name when the atlas has no icon for it
+    fn key_icon(
+        &mut self,
+        layout: &Handle<TextureAtlasLayout>,
+        icons: &Handle<Image>,
+        key: &KeyIcons,
+    ) -> EntityCommands;
+
+    /// Spawn a button showing [`Widgets::key_icon`]
+    fn key_button(
+        &mut self,
+        layout: &Handle<TextureAtlasLayout>,
+        icons: &Handle<Image>,
+        key: &KeyIcons,
+    ) -> EntityCommands;
 }
 
 impl<T: Spawn> Widgets for T {
@@ -98,6 +114,7 @@ impl<T: Spawn> Widgets for T {
                 none: NODE_BACKGROUND,
                 hovered: BUTTON_HOVERED_BACKGROUND,
                 pressed: BUTTON_PRESSED_BACKGROUND,
+                focused: FOCUS_RING,
             },
         ));
         entity.with_children(|children| {
@@ -179,26 +196,11 @@ impl<T: Spawn> Widgets for T {
     /// This controls the styling for individual inventory slots
     fn inventory_slot(
         &mut self,
+        index: usize,
         slot: &InventorySlot,
-        blocks: &Assets<Block>,
-        voxels: &Blocks,
-        materials: &Assets<StandardMaterial>,
+        icons: &BlockIcons,
     ) -> EntityCommands {
-        let (image_handle, image_color) = match &slot.resource_type {
-            Some(block_type) => {
-                let block = voxels.get(block_type.clone());
-                let block = blocks.get(block.id()).expect("Block Struct Failed to Load");
-                let material = materials
-                    .get(block.material().id())
-                    .expect("block material to exist");
-                if let Some(texture) = &material.base_color_texture {
-                    (texture.clone(), block.color())
-                } else {
-                    (TRANSPARENT_IMAGE_HANDLE, Color::srgb(0.8, 0.8, 0.8))
-                }
-            }
-            None => (TRANSPARENT_IMAGE_HANDLE, Color::srgb(0.8, 0.8, 0.8)),
-        };
+        let (image_handle, image_color) = slot_image(slot, icons);
         let mut entity = self.spawn((
             Name::new("Inventory Slot"),
             ImageBundle {
@@ -215,35 +217,36 @@ impl<T: Spawn> Widgets for T {
                 },
                 ..default()
             },
+            SlotContents(index),
         ));
 
         entity.with_children(|children| {
-            if let Some(resource_type) = &slot.resource_type {
-                children.spawn((
-                    Name::new("Resource Type"),
-                    TextBundle {
-                        style: Style {
-                            position_type: PositionType::Absolute,
-                            bottom: Val::Percent(5.0),
-                            left: Val::Percent(5.0),
-                            ..default()
-                        },
-                        text: Text {
-                            sections: vec![TextSection {
-                                value: format!("{:?}", resource_type),
-                                style: TextStyle {
-                                    font_size: 12.0,
-                                    color: Color::srgb(0.0, 1.0, 1.0), // ! Have this change depending on resource type
-                                    ..default()
-                                },
-                            }],
-                            justify: JustifyText::Left,
-                            linebreak_behavior: bevy::text::BreakLineOn::WordBoundary,
-                        },
+            // empty slots keep their text nodes so they can be filled in place
+            children.spawn((
+                Name::new("Resource Type"),
+                TextBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        bottom: Val::Percent(5.0),
+                        left: Val::Percent(5.0),
                         ..default()
                     },
-                ));
-            }
+                    text: Text {
+                        sections: vec![TextSection {
+                            value: slot_name(slot),
+                            style: TextStyle {
+                                font_size: 12.0,
+                                color: Color::srgb(0.0, 1.0, 1.0), // ! Have this change depending on resource type
+                                ..default()
+                            },
+                        }],
+                        justify: JustifyText::Left,
+                        linebreak_behavior: bevy::text::BreakLineOn::WordBoundary,
+                    },
+                    ..default()
+                },
+                SlotName,
+            ));
             children.spawn((
                 Name::new("Quantity"),
                 TextBundle {
@@ -267,6 +270,7 @@ impl<T: Spawn> Widgets for T {
                     },
                     ..default()
                 },
+                SlotQuantity,
             ));
         });
 
@@ -274,13 +278,7 @@ impl<T: Spawn> Widgets for T {
     }
 
     /// This controls the styling for the inventory hotbar. The hotbar holds 10 items
-    fn hotbar(
-        &mut self,
-        inventory: &Inventory,
-        voxels: &Blocks,
-        voxel_data: &Assets<Block>,
-        materials: &Assets<StandardMaterial>,
-    ) -> EntityCommands {
+    fn hotbar(&mut self, inventory: &Inventory, icons: &BlockIcons) -> EntityCommands {
         let mut entity = self.spawn((
             Name::new("Hotbar"),
             NodeBundle {
@@ -299,6 +297,9 @@ impl<T: Spawn> Widgets for T {
                 background_color: BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
                 ..default()
             },
+            // only so slots dragged onto it are not dropped into the world
+            Interaction::default(),
+            HotbarUI,
         ));
 
         entity.with_children(|children| {
@@ -311,7 +312,7 @@ impl<T: Spawn> Widgets for T {
             {
                 let global_index = inventory.selected_row * 10 + index;
                 let mut slot_entity = children.spawn((
-                    Name::new(format!("Hotbar Slot {}", global_index)),
+                    Name::new(format!("Hotbar Slot {}", index)),
                     NodeBundle {
                         style: Style {
                             width: Percent(100.0),
@@ -327,10 +328,11 @@ impl<T: Spawn> Widgets for T {
                         },
                         ..default()
                     },
+                    HotbarSlot(index),
                 ));
 
                 slot_entity.with_children(|slot_children| {
-                    slot_children.inventory_slot(slot, voxel_data, voxels, materials);
+                    slot_children.inventory_slot(global_index, slot, icons);
                 });
             }
         });
@@ -338,21 +340,13 @@ impl<T: Spawn> Widgets for T {
         entity
     }
 
-    fn full_inventory(
-        &mut self,
-        inventory: &Inventory,
-        voxels: &Blocks,
-        voxel_data: &Assets<Block>,
-        materials: &Assets<StandardMaterial>,
-    ) -> EntityCommands {
+    fn full_inventory(&mut self, inventory: &Inventory, icons: &BlockIcons) -> EntityCommands {
         let mut entity = self.spawn((
             Name::new("Full Inventory"),
             NodeBundle {
                 style: Style {
-                    display: Display::Grid,
-                    grid_template_columns: RepeatedGridTrack::flex(10, 1.0),
-                    grid_template_rows: RepeatedGridTrack::flex(6, 1.0),
-                    justify_content: JustifyContent::SpaceAround,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
                     position_type: PositionType::Absolute,
                     bottom: Percent(25.0),
                     left: Percent(10.0),
@@ -364,13 +358,56 @@ impl<T: Spawn> Widgets for T {
                 visibility: Visibility::Hidden,
                 ..default()
             },
+            // so slots dragged out of the grid onto the panel are not dropped into the world
+            Interaction::default(),
             FullInventoryUI,
         ));
 
         entity.with_children(|children| {
-            for slot in inventory.slots.iter().take(60) {
-                children.inventory_slot(slot, voxel_data, voxels, materials);
-            }
+            children
+                .spawn((
+                    Name::new("Inventory Grid"),
+                    NodeBundle {
+                        style: Style {
+                            display: Display::Grid,
+                            grid_template_columns: RepeatedGridTrack::flex(10, 1.0),
+                            grid_template_rows: RepeatedGridTrack::flex(6, 1.0),
+                            justify_content: JustifyContent::SpaceAround,
+                            width: Percent(100.0),
+                            flex_grow: 1.0,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ))
+                .with_children(|grid| {
+                    for (index, slot) in inventory.slots.iter().take(60).enumerate() {
+                        // a button so gamepads can focus it and confirm picks it up or puts it down
+                        grid.spawn((
+                            Name::new(format!("Inventory Slot {}", index)),
+                            ButtonBundle {
+                                style: Style {
+                                    width: Percent(100.0),
+                                    height: Percent(100.0),
+                                    ..default()
+                                },
+                                background_color: BackgroundColor(Color::NONE),
+                                ..default()
+                            },
+                            InteractionPalette {
+                                none: Color::NONE,
+                                hovered: BUTTON_HOVERED_BACKGROUND,
+                                pressed: BUTTON_PRESSED_BACKGROUND,
+                                focused: FOCUS_RING,
+                            },
+                            InventorySlotButton(index),
+                        ))
+                        .with_children(|cell| {
+                            cell.inventory_slot(index, slot, icons);
+                        });
+                    }
+                });
+            children.button("Sort").insert(SortInventoryButton);
         });
 
         entity
@@ -411,7 +448,7 @@ impl<T: Spawn> Widgets for T {
                                     true
                                 };
                             let icon = Into::<KeyIcons>::into(binding.clone());
-                            let mut button = c.icon_button(layout.clone(), icons.clone(), icon);
+                            let mut button = c.key_button(layout, icons, &icon);
                             if can_rebind {
                                 button.insert((
                                     crate::screen::options::RebindAction::Open,
@@ -443,6 +480,22 @@ impl<T: Spawn> Widgets for T {
         })
     }
 
+    fn stepper(
+        &mut self,
+        text: impl Into<String>,
+        lower: impl Bundle,
+        raise: impl Bundle,
+        label: impl Bundle,
+    ) -> EntityCommands {
+        let mut row = self.horizontal();
+        row.with_children(|row| {
+            row.button("-").insert(lower);
+            row.label(text).insert(label);
+            row.button("+").insert(raise);
+        });
+        row
+    }
+
     fn icon_button(
         &mut self,
         layout: Handle<TextureAtlasLayout>,
@@ -466,6 +519,7 @@ impl<T: Spawn> Widgets for T {
                 none: NODE_BACKGROUND,
                 hovered: BUTTON_HOVERED_BACKGROUND,
                 pressed: BUTTON_PRESSED_BACKGROUND,
+                focused: FOCUS_RING,
             },
         ));
         entity.with_children(|children| {
@@ -489,6 +543,64 @@ impl<T: Spawn> Widgets for T {
         entity
     }
 
+    fn key_icon(
+        &mut self,
+        layout: &Handle<TextureAtlasLayout>,
+        icons: &Handle<Image>,
+        key: &KeyIcons,
+    ) -> EntityCommands {
+        let (keys, separator) = match key {
+            KeyIcons::Chord(keys) => (keys, "+"),
+            KeyIcons::Directions(keys) => (keys, "/"),
+            _ if key.icon().is_some() => {
+                return self.icon(layout.clone(), icons.clone(), key.clone())
+            }
+            _ => return key_text(self, key.label()),
+        };
+        let mut row = self.horizontal();
+        row.insert(Name::new("Key Group")).with_children(|row| {
+            for (index, key) in keys.iter().enumerate() {
+                if index > 0 {
+                    key_text(row, separator.to_string());
+                }
+                row.key_icon(layout, icons, key);
+            }
+        });
+        row
+    }
+
+    fn key_button(
+        &mut self,
+        layout: &Handle<TextureAtlasLayout>,
+        icons: &Handle<Image>,
+        key: &KeyIcons,
+    ) -> EntityCommands {
+        let mut entity = self.spawn((
+            Name::new("KeyButton"),
+            ButtonBundle {
+                style: Style {
+                    min_width: Px(32.0),
+                    height: Px(32.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BackgroundColor(NODE_BACKGROUND),
+                ..default()
+            },
+            InteractionPalette {
+                none: NODE_BACKGROUND,
+                hovered: BUTTON_HOVERED_BACKGROUND,
+                pressed: BUTTON_PRESSED_BACKGROUND,
+                focused: FOCUS_RING,
+            },
+        ));
+        entity.with_children(|children| {
+            children.key_icon(layout, icons, key);
+        });
+        entity
+    }
+
     fn icon(
         &mut self,
         layout: Handle<TextureAtlasLayout>,
@@ -514,6 +626,58 @@ impl<T: Spawn> Widgets for T {
     }
 }
 
+/// The icon and background an inventory slot is drawn with, a flat colour until its icon has
+/// been drawn
+pub fn slot_image(slot: &InventorySlot, icons: &BlockIcons) -> (Handle<Image>, Color) {
+    match &slot.resource_type {
+        Some(block_type) => match icons.get(block_type) {
+            Some(icon) => (icon, Color::NONE),
+            None => (TRANSPARENT_IMAGE_HANDLE, block_type.color()),
+        },
+        None => (TRANSPARENT_IMAGE_HANDLE, Color::srgb(0.8, 0.8, 0.8)),
+    }
+}
+
+/// The name written on an inventory slot, empty when it holds nothing
+pub fn slot_name(slot: &InventorySlot) -> String {
+    slot.resource_type
+        .as_ref()
+        .map(|resource_type| format!("{:?}", resource_type))
+        .unwrap_or_default()
+}
+
+/// The name of a key without an icon, sized to sit in line with the icons
+fn key_text(spawner: &mut impl Spawn, text: String) -> EntityCommands {
+    let mut entity = spawner.spawn((
+        Name::new("Key Label"),
+        NodeBundle {
+            style: Style {
+                min_width: Px(32.0),
+                height: Px(32.0),
+                padding: UiRect::horizontal(Px(4.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        },
+    ));
+    entity.with_children(|children| {
+        children.spawn((
+            Name::new("Key Label Text"),
+            TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size: 16.0,
+                    color: LABEL_TEXT,
+                    ..default()
+                },
+            ),
+        ));
+    });
+    entity
+}
+
 /// An extension trait for spawning UI containers.
 pub trait Containers {
     /// Spawns a root node that covers the full screen